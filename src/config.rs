@@ -1,38 +1,594 @@
+use crate::wanikani::api::BASE_URL;
+use chrono::Utc;
 use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Version of the config file layout, bumped whenever fields are added or moved
+pub const CONFIG_VERSION: u32 = 3;
+
+/// Profile that files written before profiles were introduced are migrated to
+pub const DEFAULT_PROFILE: &str = "default";
 
 #[derive(Deserialize, Serialize)]
 pub struct Config {
+    /// Missing in files written before settings were introduced
+    #[serde(default)]
+    pub version: u32,
+    /// Only set through `switch_profile`, so it always names a profile in `profiles`
+    #[serde(default = "default_profile_name")]
+    active_profile: String,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Top level token and settings of files written before profiles were introduced
+    #[serde(default, skip_serializing)]
+    api_token: Option<String>,
+    #[serde(default, skip_serializing)]
+    settings: Option<Settings>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Profile {
     pub api_token: String,
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            api_token: String::new(),
+            base_url: default_base_url(),
+            settings: Settings::default(),
+        }
+    }
+}
+
+fn default_profile_name() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+fn default_base_url() -> String {
+    BASE_URL.to_string()
+}
+
+/// Profile names become directory names, so only plain names are allowed
+fn check_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("Profile names may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(())
 }
 
-pub fn get_config_path() -> PathBuf {
-    let base_dirs = BaseDirs::new().expect("Failed to get base directories");
-    base_dirs.config_dir().join("kanikani").join("config.toml")
+impl Config {
+    pub fn new(api_token: String) -> Self {
+        let mut config = Config {
+            version: CONFIG_VERSION,
+            active_profile: default_profile_name(),
+            profiles: BTreeMap::new(),
+            api_token: None,
+            settings: None,
+        };
+        config.profile_mut().api_token = api_token;
+        config
+    }
+
+    /// The active profile, created on first access
+    pub fn profile_mut(&mut self) -> &mut Profile {
+        self.profiles
+            .entry(self.active_profile.clone())
+            .or_default()
+    }
+
+    pub fn profile(&self) -> &Profile {
+        // `new`, `switch_profile` and `load_config` make sure the active profile exists
+        &self.profiles[&self.active_profile]
+    }
+
+    pub fn active_profile(&self) -> &str {
+        &self.active_profile
+    }
+
+    /// Makes `name` the active profile, creating an empty one if it does not exist yet
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        check_profile_name(name)?;
+        self.active_profile = name.to_string();
+        self.profile_mut();
+        Ok(())
+    }
+
+    /// Makes sure the profile names read from the file are safe to use as directory names
+    fn check_profile_names(&self) -> Result<(), ConfigError> {
+        for name in self.profiles.keys().chain([&self.active_profile]) {
+            check_profile_name(name).map_err(|_| ConfigError::InvalidProfile(name.clone()))?;
+        }
+        Ok(())
+    }
+
+    pub fn cache_dir(&self) -> Result<PathBuf, ConfigError> {
+        get_cache_dir(&self.active_profile)
+    }
+
+    pub fn data_dir(&self) -> Result<PathBuf, ConfigError> {
+        get_data_dir(&self.active_profile)
+    }
+
+    /// Moves the token and settings of older files into the default profile
+    fn migrate(&mut self) {
+        if let Some(api_token) = self.api_token.take() {
+            let profile = self
+                .profiles
+                .entry(DEFAULT_PROFILE.to_string())
+                .or_default();
+            profile.api_token = api_token;
+            profile.settings = self.settings.take().unwrap_or_default();
+        }
+        self.profile_mut();
+        self.version = CONFIG_VERSION;
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub ui_mode: UiMode,
+    pub review_order: ReviewOrder,
+    pub lesson_batch_size: u8,
+    pub review_batch_size: u8,
+    /// Most reviews in one session, 0 for no limit
+    pub review_session_size: u16,
+    pub font: String,
+    pub ascii_art_width: u32,
+    pub fuzzy_matching: FuzzyMatching,
+    pub autoplay_audio: bool,
+    pub colors: bool,
+    pub allow_undo: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            ui_mode: UiMode::Term,
+            review_order: ReviewOrder::Random,
+            lesson_batch_size: 5,
+            review_batch_size: 10,
+            review_session_size: 100,
+            font: "togoshi-gothic".to_string(),
+            ascii_art_width: 90,
+            fuzzy_matching: FuzzyMatching::Off,
+            autoplay_audio: false,
+            colors: true,
+            allow_undo: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Keys of the editable settings with a short description of the accepted values
+    pub const FIELDS: &'static [(&'static str, &'static str)] = &[
+        ("ui_mode", "term or tui"),
+        ("review_order", "random, oldest_first or lowest_stage_first"),
+        ("lesson_batch_size", "1 to 20 lessons per batch"),
+        ("review_batch_size", "1 to 50 reviews in rotation at once"),
+        (
+            "review_session_size",
+            "0 to 1000 reviews per session, 0 for no limit",
+        ),
+        ("font", "font used to draw kanji, e.g. togoshi-gothic"),
+        ("ascii_art_width", "20 to 300 columns"),
+        ("fuzzy_matching", "off, strict, normal or lenient"),
+        ("autoplay_audio", "true or false"),
+        ("colors", "true or false"),
+        (
+            "allow_undo",
+            "true or false, whether answers can be undone in reviews",
+        ),
+    ];
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "ui_mode" => self.ui_mode.to_string(),
+            "review_order" => self.review_order.to_string(),
+            "lesson_batch_size" => self.lesson_batch_size.to_string(),
+            "review_batch_size" => self.review_batch_size.to_string(),
+            "review_session_size" => self.review_session_size.to_string(),
+            "font" => self.font.clone(),
+            "ascii_art_width" => self.ascii_art_width.to_string(),
+            "fuzzy_matching" => self.fuzzy_matching.to_string(),
+            "autoplay_audio" => self.autoplay_audio.to_string(),
+            "colors" => self.colors.to_string(),
+            "allow_undo" => self.allow_undo.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Validates and applies a new value for the given key
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match key {
+            "ui_mode" => self.ui_mode = value.parse()?,
+            "review_order" => self.review_order = value.parse()?,
+            "lesson_batch_size" => self.lesson_batch_size = parse_in_range(value, 1, 20)?,
+            "review_batch_size" => self.review_batch_size = parse_in_range(value, 1, 50)?,
+            "review_session_size" => self.review_session_size = parse_in_range(value, 0, 1000)?,
+            "font" => {
+                if value.is_empty() {
+                    return Err("Font name cannot be empty".to_string());
+                }
+                self.font = value.to_string();
+            }
+            "ascii_art_width" => self.ascii_art_width = parse_in_range(value, 20, 300)?,
+            "fuzzy_matching" => self.fuzzy_matching = value.parse()?,
+            "autoplay_audio" => self.autoplay_audio = parse_bool(value)?,
+            "colors" => self.colors = parse_bool(value)?,
+            "allow_undo" => self.allow_undo = parse_bool(value)?,
+            _ => return Err(format!("Unknown setting: {}", key)),
+        }
+        Ok(())
+    }
+}
+
+fn parse_in_range<T: FromStr + PartialOrd + fmt::Display>(
+    value: &str,
+    min: T,
+    max: T,
+) -> Result<T, String> {
+    match value.parse::<T>() {
+        Ok(n) if n >= min && n <= max => Ok(n),
+        _ => Err(format!("Expected a number from {} to {}", min, max)),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err("Expected true or false".to_string()),
+    }
+}
+
+/// Declares a unit-only settings enum stored as a snake_case string
+macro_rules! setting_enum {
+    ($name:ident { $($variant:ident => $value:literal),+ $(,)? }) => {
+        #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value),+
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s.trim().to_lowercase().as_str() {
+                    $($value => Ok($name::$variant),)+
+                    _ => Err(format!(
+                        "Expected one of: {}",
+                        [$($value),+].join(", ")
+                    )),
+                }
+            }
+        }
+    };
 }
 
-pub fn load_config() -> Option<Config> {
-    let config_file = get_config_path();
-    if config_file.exists() {
-        let config_str = fs::read_to_string(&config_file).expect("Failed to read config file");
-        let config: Config = toml::from_str(&config_str).expect("Failed to parse config file");
-        Some(config)
+setting_enum!(UiMode {
+    Term => "term",
+    Tui => "tui",
+});
+
+setting_enum!(ReviewOrder {
+    Random => "random",
+    OldestFirst => "oldest_first",
+    LowestStageFirst => "lowest_stage_first",
+});
+
+setting_enum!(FuzzyMatching {
+    Off => "off",
+    Strict => "strict",
+    Normal => "normal",
+    Lenient => "lenient",
+});
+
+impl FuzzyMatching {
+    /// Number of typos tolerated in a meaning answer of the given length
+    pub fn max_typos(&self, answer_len: usize) -> usize {
+        match self {
+            FuzzyMatching::Off => 0,
+            FuzzyMatching::Strict => usize::from(answer_len >= 6),
+            FuzzyMatching::Normal => match answer_len {
+                0..=3 => 0,
+                4..=7 => 1,
+                _ => 2,
+            },
+            FuzzyMatching::Lenient => match answer_len {
+                0..=2 => 0,
+                3..=5 => 1,
+                6..=9 => 2,
+                _ => 3,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The home directory could not be determined
+    NoBaseDirectories,
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    Serialize(toml::ser::Error),
+    /// A profile name that is not safe to use as a directory name
+    InvalidProfile(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NoBaseDirectories => {
+                write!(f, "Failed to determine the home directory")
+            }
+            ConfigError::Io { path, source } => {
+                write!(f, "Failed to access {}: {}", path.display(), source)
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "Failed to parse {}: {}", path.display(), source)
+            }
+            ConfigError::Serialize(source) => write!(f, "Failed to serialize config: {}", source),
+            ConfigError::InvalidProfile(name) => write!(
+                f,
+                "Invalid profile name '{}', profile names may only contain letters, digits, '-' and '_'",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::NoBaseDirectories => None,
+            ConfigError::Io { source, .. } => Some(source),
+            ConfigError::Parse { source, .. } => Some(source),
+            ConfigError::Serialize(source) => Some(source),
+            ConfigError::InvalidProfile(_) => None,
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> ConfigError + '_ {
+    move |source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+pub fn get_config_path() -> Result<PathBuf, ConfigError> {
+    let base_dirs = BaseDirs::new().ok_or(ConfigError::NoBaseDirectories)?;
+    Ok(base_dirs.config_dir().join("kanikani").join("config.toml"))
+}
+
+/// Cache directory of a profile, each profile keeps its own subject cache
+pub fn get_cache_dir(profile: &str) -> Result<PathBuf, ConfigError> {
+    check_profile_name(profile).map_err(|_| ConfigError::InvalidProfile(profile.to_string()))?;
+    let base_dirs = BaseDirs::new().ok_or(ConfigError::NoBaseDirectories)?;
+    Ok(base_dirs
+        .cache_dir()
+        .join("kanikani")
+        .join("profiles")
+        .join(profile))
+}
+
+/// Data directory of a profile, for files the user keeps such as saved session summaries
+pub fn get_data_dir(profile: &str) -> Result<PathBuf, ConfigError> {
+    check_profile_name(profile).map_err(|_| ConfigError::InvalidProfile(profile.to_string()))?;
+    let base_dirs = BaseDirs::new().ok_or(ConfigError::NoBaseDirectories)?;
+    Ok(base_dirs
+        .data_dir()
+        .join("kanikani")
+        .join("profiles")
+        .join(profile))
+}
+
+/// Removes everything cached for a profile, such as the subject cache
+pub fn clear_cache(profile: &str) -> Result<(), ConfigError> {
+    let cache_dir = get_cache_dir(profile)?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir).map_err(io_error(&cache_dir))?;
+    }
+    Ok(())
+}
+
+/// Loads the config file, `Ok(None)` means there is no config file yet
+pub fn load_config() -> Result<Option<Config>, ConfigError> {
+    let config_file = get_config_path()?;
+    if !config_file.exists() {
+        return Ok(None);
+    }
+
+    let config_str = fs::read_to_string(&config_file).map_err(io_error(&config_file))?;
+    let mut config: Config = toml::from_str(&config_str).map_err(|source| ConfigError::Parse {
+        path: config_file.clone(),
+        source,
+    })?;
+    config.check_profile_names()?;
+
+    if config.version < CONFIG_VERSION {
+        // Rewriting the file is best effort, the migrated config is usable either way
+        config.migrate();
+        let _ = save_config(&config);
     } else {
-        None
+        config.profile_mut();
+    }
+    Ok(Some(config))
+}
+
+/// Writes the config atomically: a temporary file readable only by the user is renamed over it
+pub fn save_config(config: &Config) -> Result<(), ConfigError> {
+    let config_file = get_config_path()?;
+    let config_dir = config_file.parent().ok_or(ConfigError::NoBaseDirectories)?;
+    fs::create_dir_all(config_dir).map_err(io_error(config_dir))?;
+
+    let config_str = toml::to_string(config).map_err(ConfigError::Serialize)?;
+    let temp_file = config_file.with_extension("toml.tmp");
+    write_private_file(&temp_file, config_str.as_bytes()).map_err(io_error(&temp_file))?;
+    fs::rename(&temp_file, &config_file).map_err(io_error(&config_file))?;
+    Ok(())
+}
+
+/// Moves a config file that failed to load out of the way, returning where it was moved to
+pub fn backup_config() -> Result<PathBuf, ConfigError> {
+    let config_file = get_config_path()?;
+    let backup_file =
+        config_file.with_extension(format!("toml.broken-{}", Utc::now().format("%Y%m%d%H%M%S")));
+    fs::rename(&config_file, &backup_file).map_err(io_error(&config_file))?;
+    Ok(backup_file)
+}
+
+/// The config file holds the API token, so it must not be readable by other users
+fn write_private_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
     }
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        // `mode` only applies when the file is created, fix up leftovers from a previous run
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)?;
+    file.sync_all()
 }
 
-pub fn save_config(config: &Config) {
-    let config_file = get_config_path();
-    let config_dir = config_file
-        .parent()
-        .expect("Failed to get config directory");
-    fs::create_dir_all(config_dir).expect("Failed to create config directory");
-    let config_str = toml::to_string(config).expect("Failed to serialize config");
-    let mut file = fs::File::create(&config_file).expect("Failed to create config file");
-    file.write_all(config_str.as_bytes())
-        .expect("Failed to write config file");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_old_config_is_migrated_to_default_profile() {
+        let mut config: Config = toml::from_str("api_token = \"abc\"").unwrap();
+        assert_eq!(config.version, 0);
+        config.migrate();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.active_profile, DEFAULT_PROFILE);
+        assert_eq!(config.profile().api_token, "abc");
+        assert_eq!(config.profile().base_url, BASE_URL);
+        assert_eq!(config.profile().settings, Settings::default());
+
+        let mut config: Config = toml::from_str(
+            "version = 2\napi_token = \"abc\"\n[settings]\nreview_order = \"oldest_first\"",
+        )
+        .unwrap();
+        config.migrate();
+        assert_eq!(
+            config.profile().settings.review_order,
+            ReviewOrder::OldestFirst
+        );
+        assert!(!toml::to_string(&config).unwrap().contains("[settings]"));
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let mut config = Config::new("abc".to_string());
+        config.switch_profile("test-account").unwrap();
+        config.profile_mut().api_token = "def".to_string();
+        let settings = &mut config.profile_mut().settings;
+        settings.set("review_order", "oldest_first").unwrap();
+        settings.set("ascii_art_width", "120").unwrap();
+
+        let parsed: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(parsed.active_profile, "test-account");
+        assert_eq!(parsed.profiles[DEFAULT_PROFILE].api_token, "abc");
+        assert_eq!(parsed.profile().api_token, "def");
+        assert_eq!(
+            parsed.profile().settings.review_order,
+            ReviewOrder::OldestFirst
+        );
+        assert_eq!(parsed.profile().settings.ascii_art_width, 120);
+    }
+
+    #[test]
+    fn test_profile_names() {
+        let mut config = Config::new("abc".to_string());
+        assert!(config.switch_profile("../main").is_err());
+        assert!(config.switch_profile("").is_err());
+        assert_eq!(config.active_profile, DEFAULT_PROFILE);
+        assert!(config.switch_profile("main_2").is_ok());
+        assert_eq!(config.profile(), &Profile::default());
+
+        let config: Config = toml::from_str("active_profile = \"../..\"").unwrap();
+        assert!(config.check_profile_names().is_err());
+        let config: Config = toml::from_str("[profiles.\"../main\"]\napi_token = \"abc\"").unwrap();
+        assert!(config.check_profile_names().is_err());
+        assert!(get_cache_dir("..").is_err());
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        let mut settings = Settings::default();
+        assert!(settings.set("lesson_batch_size", "0").is_err());
+        assert!(settings.set("lesson_batch_size", "abc").is_err());
+        assert!(settings.set("ui_mode", "gui").is_err());
+        assert!(settings.set("font", " ").is_err());
+        assert!(settings.set("unknown", "1").is_err());
+        assert_eq!(settings, Settings::default());
+        assert!(settings.set("colors", "off").is_ok());
+        assert!(!settings.colors);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("kanikani-test-{}.toml", std::process::id()));
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private_file(&path, b"api_token = \"abc\"").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "api_token = \"abc\"");
+        fs::remove_file(&path).unwrap();
+    }
 }
@@ -1,29 +1,80 @@
 mod config;
+mod settings;
 mod term;
 mod tui;
 mod wanikani;
 
-use crate::config::{load_config, save_config, Config};
-use crate::wanikani::api::WaniKaniClient;
+use crate::config::{backup_config, load_config, save_config, Config, ConfigError, UiMode};
+use crate::wanikani::api::{is_valid_token_format, ApiError, WaniKaniClient};
+use crate::wanikani::dashboard;
 
 const KANILOGO_PATH: &str = "src/art/kanilogo.txt";
 const KANINAME_PATH: &str = "src/art/kaniname.txt";
 
 mod menu {
+    use crate::config::{clear_cache, save_config, Config};
     use crate::WaniKaniClient;
-    pub type MenuAction = fn(&str, &WaniKaniClient) -> ();
+    pub type MenuAction = fn(&str, &WaniKaniClient, &mut Config) -> ();
     use crate::display;
+    use crate::settings;
+    use crate::wanikani::cloze;
+    use crate::wanikani::dictionary;
+    use crate::wanikani::extra_study;
+    use crate::wanikani::history::History;
+    use crate::wanikani::kana_trainer;
+    use crate::wanikani::leeches;
     use crate::wanikani::lessons;
+    use crate::wanikani::reverse_quiz;
     use crate::wanikani::reviews;
+    use crate::wanikani::session_summary;
+    use crate::wanikani::stats;
+    use crate::wanikani::timeline;
 
-    fn placeholder_action(output_method: &str, _client: &WaniKaniClient) {
+    fn placeholder_action(output_method: &str, _client: &WaniKaniClient, _config: &mut Config) {
         println!("{}", output_method);
     }
 
+    /// Explains that the queues are frozen if vacation mode is on
+    /// If the user can't be loaded the session goes ahead and reports its own errors
+    fn is_on_vacation(output_method: &str, client: &WaniKaniClient) -> bool {
+        let notice = client
+            .fetch_user_info()
+            .ok()
+            .and_then(|user| user.data.vacation_notice());
+        match notice {
+            Some(notice) => {
+                display::display_text(output_method, &notice);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Shows the summary of a finished session, offering to save it in the data directory
+    fn finish_session(
+        output_method: &str,
+        config: &Config,
+        summary: &session_summary::SessionSummary,
+    ) {
+        match config.data_dir() {
+            Ok(data_dir) => session_summary::show_summary(output_method, summary, &data_dir),
+            Err(e) => display::display_text(output_method, &format!("Error: {}", e)),
+        }
+    }
+
     pub const INTRO_MENU: &[(&char, &str, MenuAction)] = &[
-        (&'0', "Reviews", |output_method, client| {
+        (&'0', "Reviews", |output_method, client, config| {
+            if is_on_vacation(output_method, client) {
+                return;
+            }
             let client_clone = client.clone();
-            let summary = client.fetch_summary().unwrap();
+            let summary = match client.fetch_summary() {
+                Ok(summary) => summary,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
             let available_reviews = summary.get_available_reviews();
 
             if available_reviews.is_empty() {
@@ -31,26 +82,60 @@ mod menu {
                 return;
             }
 
-            let assignments = client.fetch_available_assignments(true).unwrap();
-            let review_items: Vec<(u64, u64)> = assignments
-                .data
-                .iter()
-                .map(|a| (a.id, a.data.subject_id))
-                .collect();
-
-            let mut session =
-                reviews::ReviewSession::new(client_clone, review_items, output_method.to_string());
-
-            if let Err(e) = session.start() {
-                display::display_text(
+            // Answers are recorded as they are submitted, so they are kept if the session fails
+            let (cache_dir, data_dir) = match (config.cache_dir(), config.data_dir()) {
+                (Ok(cache_dir), Ok(data_dir)) => (cache_dir, data_dir),
+                (Err(e), _) | (_, Err(e)) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let history = match History::load(&data_dir) {
+                Ok(history) => history,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let mut assignments = match client.fetch_available_assignments(true) {
+                Ok(assignments) => assignments,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let settings = config.profile().settings.clone();
+            reviews::order_assignments(&mut assignments, settings.review_order);
+
+            let mut session = reviews::ReviewSession::new(
+                client_clone,
+                &assignments,
+                output_method.to_string(),
+                cache_dir,
+                settings,
+            )
+            .record_history(history);
+
+            match session.start() {
+                Ok(summary) => finish_session(output_method, config, &summary),
+                Err(e) => display::display_text(
                     output_method,
                     &format!("Error during review session: {}", e),
-                );
+                ),
             }
         }),
-        (&'1', "Lessons", |output_method, client| {
+        (&'1', "Lessons", |output_method, client, config| {
+            if is_on_vacation(output_method, client) {
+                return;
+            }
             let client_clone = client.clone();
-            let summary = client.fetch_summary().unwrap();
+            let summary = match client.fetch_summary() {
+                Ok(summary) => summary,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
             let available_lessons = summary.get_available_lessons();
 
             if available_lessons.is_empty() {
@@ -58,23 +143,188 @@ mod menu {
                 return;
             }
 
+            let cache_dir = match config.cache_dir() {
+                Ok(cache_dir) => cache_dir,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
             let mut session = lessons::LessonSession::new(
                 client_clone,
                 available_lessons,
                 output_method.to_string(),
+                config.profile().settings.clone(),
+                cache_dir,
+            );
+
+            match session.start() {
+                Ok(summary) => finish_session(output_method, config, &summary),
+                Err(e) => display::display_text(
+                    output_method,
+                    &format!("Error during lesson session: {}", e),
+                ),
+            }
+        }),
+        (&'2', "Stats", |output_method, client, config| {
+            let cache_dir = match config.cache_dir() {
+                Ok(cache_dir) => cache_dir,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            if let Err(e) = stats::show_stats(output_method, client, &cache_dir) {
+                display::display_text(output_method, &format!("Error in stats: {}", e));
+            }
+        }),
+        (&'3', "Dictionary", |output_method, client, config| {
+            let cache_dir = match config.cache_dir() {
+                Ok(cache_dir) => cache_dir,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let mut session = dictionary::DictionarySession::new(
+                client.clone(),
+                output_method.to_string(),
+                cache_dir,
+                config.profile().settings.colors,
             );
 
             if let Err(e) = session.start() {
+                display::display_text(output_method, &format!("Error in dictionary: {}", e));
+            }
+        }),
+        (&'4', "Settings", |output_method, _client, config| {
+            settings::edit_settings(output_method, config);
+        }),
+        (&'5', "Logout", |output_method, _client, config| {
+            display::display_text(
+                output_method,
+                "Logging out removes the API token of this profile from this computer, along with its cached subjects, review statistics and SRS systems.\nThe cache is downloaded again when you log back in. Your settings, review history and kana trainer stats are kept.",
+            );
+            let answer =
+                display::text_input(output_method, "Are you sure you want to log out? (y/N)");
+            if !answer.eq_ignore_ascii_case("y") {
+                return;
+            }
+
+            let api_token = std::mem::take(&mut config.profile_mut().api_token);
+            if let Err(e) = save_config(config) {
+                config.profile_mut().api_token = api_token;
                 display::display_text(
                     output_method,
-                    &format!("Error during lesson session: {}", e),
+                    &format!(
+                        "Failed to remove the API token, you are still logged in: {}",
+                        e
+                    ),
+                );
+                return;
+            }
+            if let Err(e) = clear_cache(config.active_profile()) {
+                display::display_text(
+                    output_method,
+                    &format!("Failed to remove cached data: {}", e),
                 );
             }
+            display::display_text(output_method, "Logged out.");
+        }),
+        (&'6', "Extra Study", |output_method, client, config| {
+            let (cache_dir, data_dir) = match (config.cache_dir(), config.data_dir()) {
+                (Ok(cache_dir), Ok(data_dir)) => (cache_dir, data_dir),
+                (Err(e), _) | (_, Err(e)) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let settings = config.profile().settings.clone();
+            match extra_study::start_extra_study(
+                output_method,
+                client,
+                &settings,
+                &cache_dir,
+                &data_dir,
+            ) {
+                Ok(Some(summary)) => finish_session(output_method, config, &summary),
+                Ok(None) => {}
+                Err(e) => display::display_text(
+                    output_method,
+                    &format!("Error during extra study: {}", e),
+                ),
+            }
+        }),
+        (&'7', "Reverse Quiz", |output_method, client, config| {
+            let cache_dir = match config.cache_dir() {
+                Ok(cache_dir) => cache_dir,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let mut quiz = reverse_quiz::ReverseQuiz::new(
+                client.clone(),
+                output_method.to_string(),
+                cache_dir,
+                config.profile().settings.clone(),
+            );
+
+            if let Err(e) = quiz.start() {
+                display::display_text(output_method, &format!("Error in reverse quiz: {}", e));
+            }
+        }),
+        (&'8', "Sentence Quiz", |output_method, client, config| {
+            let cache_dir = match config.cache_dir() {
+                Ok(cache_dir) => cache_dir,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let mut quiz = cloze::ClozeQuiz::new(
+                client.clone(),
+                output_method.to_string(),
+                cache_dir,
+                config.profile().settings.clone(),
+            );
+
+            if let Err(e) = quiz.start() {
+                display::display_text(output_method, &format!("Error in sentence quiz: {}", e));
+            }
+        }),
+        (&'9', "Kana Trainer", |output_method, _client, config| {
+            let data_dir = match config.data_dir() {
+                Ok(data_dir) => data_dir,
+                Err(e) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            if let Err(e) = kana_trainer::start_kana_trainer(output_method, &data_dir) {
+                display::display_text(output_method, &format!("Error in kana trainer: {}", e));
+            }
+        }),
+        (&'l', "Leeches", |output_method, client, config| {
+            let (cache_dir, data_dir) = match (config.cache_dir(), config.data_dir()) {
+                (Ok(cache_dir), Ok(data_dir)) => (cache_dir, data_dir),
+                (Err(e), _) | (_, Err(e)) => {
+                    display::display_text(output_method, &format!("Error: {}", e));
+                    return;
+                }
+            };
+            let settings = config.profile().settings.clone();
+            match leeches::show_leeches(output_method, client, &settings, &cache_dir, &data_dir) {
+                Ok(Some(summary)) => finish_session(output_method, config, &summary),
+                Ok(None) => {}
+                Err(e) => display::display_text(output_method, &format!("Error in leeches: {}", e)),
+            }
+        }),
+        (&'t', "Level Timeline", |output_method, client, _config| {
+            if let Err(e) = timeline::show_timeline(output_method, client) {
+                display::display_text(output_method, &format!("Error in level timeline: {}", e));
+            }
         }),
-        (&'2', "Stats", placeholder_action),
-        (&'3', "Dictionary", placeholder_action),
-        (&'4', "Settings", placeholder_action),
-        (&'5', "Logout", placeholder_action),
         (&'q', "Quit", placeholder_action),
     ];
 }
@@ -83,17 +333,23 @@ mod display {
     use crate::menu;
     use crate::term;
     use crate::tui;
+    use crate::wanikani::markup;
+    use crate::wanikani::utils;
     use std::cmp;
 
     use terminal_size::terminal_size;
 
+    fn terminal_width() -> usize {
+        match terminal_size() {
+            Some((w, _)) => w.0 as usize,
+            None => 80, // Provide a default width if terminal size is not available
+        }
+    }
+
     fn center_ascii_art(ascii: &str) -> String {
         let mut result = String::new();
 
-        let term_width = match terminal_size() {
-            Some((w, _)) => w.0 as usize,
-            None => 80, // Provide a default width if terminal size is not available
-        };
+        let term_width = terminal_width();
 
         let max_line_width = ascii
             .lines()
@@ -158,50 +414,219 @@ mod display {
             _ => panic!("Invalid output method"),
         }
     }
+
+    /// Lets the user edit several lines of text, in their own editor if `$VISUAL` or `$EDITOR` is set
+    /// Returns `None` if the user cancels
+    pub fn text_area(output_method: &str, prompt: &str, initial: &str) -> Option<String> {
+        if let Some(editor) = utils::utils::external_editor() {
+            match utils::utils::edit_in_external_editor(&editor, initial) {
+                Ok(text) => return Some(text),
+                Err(e) => display_text(output_method, &format!("Could not use {}: {}", editor, e)),
+            }
+        }
+        match output_method {
+            "term" => term::text_area(prompt, initial),
+            "tui" => tui::text_area(prompt, initial),
+            _ => panic!("Invalid output method"),
+        }
+    }
+
+    /// Displays text containing WaniKani markup such as `<kanji>` tags, wrapped to the terminal width
+    pub fn display_markup(output_method: &str, text: &str, colors: bool) {
+        let lines = markup::wrap(&markup::parse(text), terminal_width());
+        match output_method {
+            "term" if colors => term::display_text(&markup::to_ansi(&lines)),
+            "term" => term::display_text(&markup::to_plain(&lines)),
+            "tui" => tui::display_lines(markup::to_lines(&lines, colors)),
+            _ => panic!("Invalid output method"),
+        }
+    }
 }
 
 fn main() {
-    let output_method = "term";
+    // Only start over with a fresh config once the old file is safely out of the way,
+    // otherwise saving the new token would overwrite every profile in it
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e @ ConfigError::Parse { .. }) => {
+            display::display_text("term", &format!("Could not load your configuration: {}", e));
+            match backup_config() {
+                Ok(backup_file) => {
+                    display::display_text(
+                        "term",
+                        &format!(
+                            "The broken file was moved to {}, please enter your token again.",
+                            backup_file.display()
+                        ),
+                    );
+                    None
+                }
+                Err(e) => {
+                    display::display_text(
+                        "term",
+                        &format!(
+                            "Failed to back up the broken configuration, it was left as it is: {}",
+                            e
+                        ),
+                    );
+                    return;
+                }
+            }
+        }
+        Err(e) => {
+            display::display_text("term", &format!("Could not load your configuration: {}", e));
+            return;
+        }
+    };
+    let output_method = config
+        .as_ref()
+        .map(|config| config.profile().settings.ui_mode)
+        .unwrap_or(UiMode::Term)
+        .as_str();
     display::display_start_screen(output_method);
 
-    // Load the configuration or prompt for the API token
-    let api_token = match load_config() {
-        Some(config) => config.api_token,
-        None => {
-            let input_msg = "Please enter your WaniKani API token:";
-            let api_token = display::text_input(output_method, input_msg);
-            save_config(&Config {
-                api_token: api_token.clone(),
-            });
-            api_token
+    let mut config = config.unwrap_or_else(|| Config::new(String::new()));
+    if let Some(profile) = profile_arg() {
+        if let Err(e) = config.switch_profile(&profile) {
+            display::display_text(output_method, &e);
+            return;
         }
-    };
+    }
 
-    // Create the WaniKani client
-    let client = WaniKaniClient::new(api_token);
+    loop {
+        // Prompt for the API token if there is none, e.g. on first launch or after logging out
+        if config.profile().api_token.is_empty() && !prompt_for_token(output_method, &mut config) {
+            return;
+        }
 
-    // Authenticate the user
-    if let Err(e) = client.authenticate() {
-        display::display_text(
-            output_method,
-            &String::from(format!("Authentication failed: {}", e)),
-        );
-        return;
-    } else {
-        display::display_text(output_method, "Authentication successful!");
+        // Create the WaniKani client
+        let profile = config.profile();
+        let mut client = WaniKaniClient::new(profile.api_token.clone(), profile.base_url.clone());
+        let active_profile = config.active_profile().to_string();
+        let base_url = profile.base_url.clone();
+
+        // Authenticate the user
+        let user = match client.fetch_user_info() {
+            Ok(user) => {
+                display::display_text(
+                    output_method,
+                    &format!("Logged in as {}!", user.data.username),
+                );
+                let subscription = &user.data.subscription;
+                client.set_max_level_granted(subscription.max_level_granted);
+                if subscription.has_lapsed() {
+                    let ended = subscription
+                        .period_ends_at
+                        .map(|date| format!(" on {}", date.format("%Y-%m-%d")))
+                        .unwrap_or_default();
+                    display::display_text(
+                        output_method,
+                        &format!(
+                            "Your WaniKani subscription has ended{}. Only levels 1 to {} are available.",
+                            ended, subscription.max_level_granted
+                        ),
+                    );
+                }
+                user
+            }
+            Err(e) if ApiError::is_unauthorized(&e) => {
+                display::display_text(
+                    output_method,
+                    "The stored API token was rejected, it may have been revoked. Please enter a new one.",
+                );
+                if !prompt_for_token(output_method, &mut config) {
+                    return;
+                }
+                continue;
+            }
+            Err(e) => {
+                display::display_text(output_method, &format!("Authentication failed: {}", e));
+                return;
+            }
+        };
+
+        loop {
+            // Re-read every time so a UI mode change in the settings applies straight away
+            let output_method = config.profile().settings.ui_mode.as_str();
+            let display_menu: &[(&char, &str, menu::MenuAction)] = menu::INTRO_MENU;
+
+            dashboard::show_dashboard(output_method, &client, &user.data);
+
+            let user_choice = display::display_menu(output_method, &display_menu);
+            if let Some((_, _, action)) =
+                display_menu.iter().find(|(key, _, _)| *key == &user_choice)
+            {
+                action(output_method, &client, &mut config);
+                if user_choice == 'q' {
+                    return;
+                }
+                let profile = config.profile();
+                if profile.api_token.is_empty()
+                    || profile.base_url != base_url
+                    || config.active_profile() != active_profile
+                {
+                    // Logged out or switched profile, log in again with the new token
+                    break;
+                }
+            } else {
+                println!("Invalid choice. Please try again.");
+            }
+        }
     }
+}
 
+/// Asks for an API token until one is accepted by WaniKani, then stores it in the active profile
+/// Returns false if the user gave up, e.g. because WaniKani cannot be reached
+fn prompt_for_token(output_method: &str, config: &mut Config) -> bool {
     loop {
-        let display_menu: &[(&char, &str, menu::MenuAction)] = menu::INTRO_MENU;
+        let input_msg = format!(
+            "Please enter the WaniKani API token for profile '{}', or 'q' to quit:",
+            config.active_profile()
+        );
+        let api_token = display::text_input(output_method, &input_msg)
+            .trim()
+            .to_string();
+        if api_token.is_empty() || api_token == "q" {
+            return false;
+        }
+        if !is_valid_token_format(&api_token) {
+            display::display_text(
+                output_method,
+                "That does not look like a WaniKani API token. Tokens look like 01234567-89ab-cdef-0123-456789abcdef.",
+            );
+            continue;
+        }
 
-        let user_choice = display::display_menu(output_method, &display_menu);
-        if let Some((_, _, action)) = display_menu.iter().find(|(key, _, _)| *key == &user_choice) {
-            action(output_method, &client);
-            if user_choice == '5' || user_choice == 'q' {
-                break;
+        let client = WaniKaniClient::new(api_token.clone(), config.profile().base_url.clone());
+        match client.fetch_user_info() {
+            Ok(user) => {
+                display::display_text(output_method, &user.data.summary());
+                config.profile_mut().api_token = api_token;
+                if let Err(e) = save_config(config) {
+                    display::display_text(
+                        output_method,
+                        &format!("Failed to save configuration: {}", e),
+                    );
+                }
+                return true;
+            }
+            Err(e) => {
+                display::display_text(output_method, &format!("Could not verify the token: {}", e))
             }
-        } else {
-            println!("Invalid choice. Please try again.");
         }
     }
 }
+
+/// Profile selected with `--profile NAME` or `--profile=NAME`
+fn profile_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            return args.next();
+        }
+        if let Some(profile) = arg.strip_prefix("--profile=") {
+            return Some(profile.to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,158 @@
+use crate::config::{save_config, Config, Settings};
+use crate::display;
+
+/// Interactive editor for the settings stored in the config file
+/// Every valid change is saved straight away
+pub fn edit_settings(output_method: &str, config: &mut Config) {
+    loop {
+        let listing = Settings::FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, (key, description))| {
+                format!(
+                    "{}. {} = {} ({})",
+                    i + 1,
+                    key,
+                    config.profile().settings.get(key).unwrap_or_default(),
+                    description
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        display::display_text(
+            output_method,
+            &format!(
+                "\nSettings for profile '{}' ({}):\n{}",
+                config.active_profile(),
+                config.profile().base_url,
+                listing
+            ),
+        );
+
+        let choice = display::text_input(
+            output_method,
+            "Enter a number to change a setting, 'p' to switch profile, 'u' to change the API URL, or 'q' to go back:",
+        );
+        match choice.as_str() {
+            "q" => break,
+            "p" => {
+                if switch_profile(output_method, config) {
+                    break;
+                }
+                continue;
+            }
+            "u" => {
+                change_base_url(output_method, config);
+                continue;
+            }
+            _ => {}
+        }
+
+        let field = choice
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| Settings::FIELDS.get(i));
+        let (key, description) = match field {
+            Some(field) => field,
+            None => {
+                display::display_text(output_method, "Invalid choice. Please try again.");
+                continue;
+            }
+        };
+
+        let value = display::text_input(
+            output_method,
+            &format!(
+                "New value for {} ({}), or press Enter to keep it:",
+                key, description
+            ),
+        );
+        if value.is_empty() {
+            continue;
+        }
+
+        match config.profile_mut().settings.set(key, &value) {
+            Ok(()) => {
+                if let Err(e) = save_config(config) {
+                    display::display_text(
+                        output_method,
+                        &format!("Failed to save settings: {}", e),
+                    );
+                    continue;
+                }
+                display::display_text(
+                    output_method,
+                    &format!(
+                        "Saved {} = {}",
+                        key,
+                        config.profile().settings.get(key).unwrap_or_default()
+                    ),
+                );
+            }
+            Err(e) => {
+                display::display_text(output_method, &format!("Invalid value for {}: {}", key, e))
+            }
+        }
+    }
+}
+
+/// Lets the user pick or create a profile, returns true if the active profile changed
+fn switch_profile(output_method: &str, config: &mut Config) -> bool {
+    let profiles = config
+        .profiles
+        .keys()
+        .map(|name| {
+            if name == config.active_profile() {
+                format!("* {} (active)", name)
+            } else {
+                format!("  {}", name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    display::display_text(output_method, &format!("Profiles:\n{}", profiles));
+
+    let name = display::text_input(
+        output_method,
+        "Enter the profile to switch to (a new name creates a profile), or press Enter to cancel:",
+    );
+    if name.is_empty() || name == config.active_profile() {
+        return false;
+    }
+
+    if let Err(e) = config.switch_profile(&name) {
+        display::display_text(output_method, &e);
+        return false;
+    }
+    if let Err(e) = save_config(config) {
+        display::display_text(output_method, &format!("Failed to save settings: {}", e));
+    }
+    display::display_text(
+        output_method,
+        &format!("Switched to profile '{}'.", config.active_profile()),
+    );
+    true
+}
+
+fn change_base_url(output_method: &str, config: &mut Config) {
+    let base_url = display::text_input(
+        output_method,
+        "New API URL for this profile, or press Enter to keep it:",
+    );
+    if base_url.is_empty() {
+        return;
+    }
+    if !base_url.starts_with("https://") && !base_url.starts_with("http://") {
+        display::display_text(
+            output_method,
+            "The API URL must start with http:// or https://",
+        );
+        return;
+    }
+
+    config.profile_mut().base_url = base_url;
+    if let Err(e) = save_config(config) {
+        display::display_text(output_method, &format!("Failed to save settings: {}", e));
+    }
+}
@@ -47,6 +47,32 @@ pub fn display_menu(options: &[(&char, &str)]) -> char {
     }
 }
 
+/// Reads several lines of text, returns `None` if the user cancels
+pub fn text_area(prompt: &str, initial: &str) -> Option<String> {
+    println!("{}", prompt);
+    if !initial.is_empty() {
+        println!("Current text:\n{}\n", initial);
+    }
+    println!("Enter the new text, then a line with only '.' to finish, or '!' to cancel:");
+
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        let read = io::stdin()
+            .read_line(&mut line)
+            .expect("Failed to read input");
+        let line = line.trim_end_matches(['\r', '\n']);
+        if read == 0 || line == "." {
+            break;
+        }
+        if line == "!" {
+            return None;
+        }
+        lines.push(line.to_string());
+    }
+    Some(lines.join("\n"))
+}
+
 pub fn display_text(text: &str) {
     println!("{}", text);
 }
@@ -1,4 +1,4 @@
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use crossterm::{
     execute,
     terminal::{enable_raw_mode, Clear, ClearType},
@@ -6,6 +6,7 @@ use crossterm::{
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout};
 use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
 use ratatui::Terminal;
 use std::io;
@@ -60,6 +61,39 @@ pub fn text_input(prompt: &str) -> String {
     input
 }
 
+/// Multi-line editor, Ctrl+S saves and Esc cancels
+pub fn text_area(prompt: &str, initial: &str) -> Option<String> {
+    let mut terminal = setup_terminal().expect("Failed to setup terminal");
+    let mut text = initial.to_string();
+    let title = format!("{} (Ctrl+S to save, Esc to cancel)", prompt);
+    let result = loop {
+        terminal
+            .draw(|f| {
+                let editor = Paragraph::new(format!("{}_", text))
+                    .block(Block::default().title(title.as_str()).borders(Borders::ALL));
+                f.render_widget(editor, f.size());
+            })
+            .expect("Failed to draw editor");
+
+        if let Event::Key(key) = event::read().expect("Failed to read event") {
+            match key.code {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Some(text)
+                }
+                KeyCode::Esc => break None,
+                KeyCode::Enter => text.push('\n'),
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                _ => {}
+            }
+        }
+    };
+    terminal.clear().expect("Failed to clear terminal");
+    result
+}
+
 pub fn display_menu(options: &[(&char, &str)]) -> char {
     let mut terminal = setup_terminal().expect("Failed to setup terminal");
     let mut selected_index = 0;
@@ -122,6 +156,18 @@ pub fn display_text(text: &str) {
     terminal.clear().expect("Failed to clear terminal");
 }
 
+pub fn display_lines(lines: Vec<Line<'static>>) {
+    let mut terminal = setup_terminal().expect("Failed to setup terminal");
+    terminal
+        .draw(|f| {
+            let paragraph = Paragraph::new(lines);
+            f.render_widget(paragraph, f.size());
+        })
+        .expect("Failed to draw text");
+    wait_for_key_press();
+    terminal.clear().expect("Failed to clear terminal");
+}
+
 fn setup_terminal() -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
     let stdout = io::stdout();
@@ -1,9 +1,34 @@
 pub mod api;
 pub mod assignment;
+pub mod browser;
+pub mod cache;
+pub mod cloze;
+pub mod collection;
+pub mod dashboard;
 pub mod decode;
+pub mod dictionary;
+pub mod extra_study;
+pub mod reset;
+pub mod review;
+pub mod review_statistic;
+pub mod reverse_quiz;
+pub mod spaced_repetition_system;
+pub mod srs;
+pub mod stats;
+pub mod store;
+pub mod study_material;
 pub mod subject;
+pub mod timeline;
 pub mod user;
 pub mod summary;
+pub mod history;
+pub mod kana_trainer;
+pub mod leeches;
 pub mod lessons;
+pub mod level_progression;
+pub mod markup;
+pub mod notes;
+pub mod quiz;
 pub mod reviews;
+pub mod session_summary;
 pub mod utils;
@@ -1,16 +1,82 @@
-use crate::wanikani::assignment::AssignmentCollection;
+use crate::wanikani::assignment::Assignment;
+use crate::wanikani::collection::Collection;
+use crate::wanikani::level_progression::LevelProgression;
+use crate::wanikani::reset::Reset;
+use crate::wanikani::review::Review;
+use crate::wanikani::review_statistic::{ReviewStatistic, ReviewStatisticFilters};
+use crate::wanikani::spaced_repetition_system::SpacedRepetitionSystem;
+use crate::wanikani::study_material::{StudyMaterial, StudyMaterialUpdate};
 use crate::wanikani::subject::subject;
 use crate::wanikani::summary;
 use crate::wanikani::user::User;
 use anyhow::{anyhow, Result};
-use reqwest::blocking::Client as BlockingClient;
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::blocking::{Client as BlockingClient, RequestBuilder};
 use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::fmt;
 
-const BASE_URL: &str = "https://api.wanikani.com/v2";
+pub const BASE_URL: &str = "https://api.wanikani.com/v2";
+
+/// Most ids sent in one `ids` filter
+const MAX_IDS_PER_REQUEST: usize = 500;
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The API token was rejected (HTTP 401)
+    Unauthorized,
+    Status(StatusCode),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::Unauthorized => write!(f, "The API token was rejected by WaniKani"),
+            ApiError::Status(status) => write!(f, "Request failed with status code: {}", status),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ApiError {
+    /// Whether `error` is a rejected API token
+    pub fn is_unauthorized(error: &anyhow::Error) -> bool {
+        matches!(
+            error.downcast_ref::<ApiError>(),
+            Some(ApiError::Unauthorized)
+        )
+    }
+}
+
+fn check_status(status: StatusCode) -> Result<(), ApiError> {
+    if status == StatusCode::UNAUTHORIZED {
+        Err(ApiError::Unauthorized)
+    } else if !status.is_success() {
+        Err(ApiError::Status(status))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that a token looks like a WaniKani API v2 token, a UUID such as
+/// `01234567-89ab-cdef-0123-456789abcdef`
+pub fn is_valid_token_format(token: &str) -> bool {
+    let groups: Vec<&str> = token.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
 
 pub struct WaniKaniClient {
     client: BlockingClient,
     api_token: String,
+    base_url: String,
+    /// Highest level the subscription grants access to, once the user is known
+    max_level_granted: Option<u8>,
 }
 
 impl Clone for WaniKaniClient {
@@ -18,72 +84,78 @@ impl Clone for WaniKaniClient {
         WaniKaniClient {
             client: BlockingClient::new(),
             api_token: self.api_token.clone(),
+            base_url: self.base_url.clone(),
+            max_level_granted: self.max_level_granted,
         }
     }
 }
 
-impl WaniKaniClient {
-    pub fn new(api_token: String) -> Self {
-        let client = BlockingClient::new();
-        WaniKaniClient { client, api_token }
-    }
-
-    pub fn authenticate(&self) -> Result<()> {
-        let url = format!("{}/user", BASE_URL);
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            AUTHORIZATION,
-            format!("Bearer {}", self.api_token).parse().unwrap(),
-        );
+/// Comma separated ids for filters such as `subject_ids`
+pub fn join_ids(ids: &[u64]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
 
-        let response = self.client.get(&url).headers(headers).send()?;
+/// Appends query parameters to a url
+fn with_query(url: String, params: &[String]) -> String {
+    if params.is_empty() {
+        url
+    } else {
+        format!("{}?{}", url, params.join("&"))
+    }
+}
 
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(anyhow!(
-                "Authentication failed with status code: {}",
-                response.status()
-            ))
+impl WaniKaniClient {
+    pub fn new(api_token: String, base_url: String) -> Self {
+        let client = BlockingClient::new();
+        WaniKaniClient {
+            client,
+            api_token,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            max_level_granted: None,
         }
     }
 
-    pub fn fetch_user_info(&self) -> Result<User> {
-        let url = format!("{}/user", BASE_URL);
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            AUTHORIZATION,
-            format!("Bearer {}", self.api_token).parse().unwrap(),
-        );
+    /// Limits subject and assignment requests to the levels the subscription grants access to
+    pub fn set_max_level_granted(&mut self, max_level_granted: u8) {
+        self.max_level_granted = Some(max_level_granted);
+    }
 
-        let response_body = self.client.get(&url).headers(headers).send()?.text()?;
+    pub fn max_level_granted(&self) -> Option<u8> {
+        self.max_level_granted
+    }
 
-        let user_info: User = serde_json::from_str(&response_body)?;
-        Ok(user_info)
+    pub fn is_level_granted(&self, level: u8) -> bool {
+        self.max_level_granted.is_none_or(|max| level <= max)
     }
 
-    pub fn fetch_assignments(&self) -> Result<AssignmentCollection> {
-        let url = format!("{}/assignments", BASE_URL);
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            AUTHORIZATION,
-            format!("Bearer {}", self.api_token).parse().unwrap(),
-        );
+    /// `levels` filter covering the levels the subscription grants access to
+    fn levels_param(&self) -> Option<String> {
+        self.max_level_granted.map(|max| {
+            let levels = (1..=max)
+                .map(|level| level.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("levels={}", levels)
+        })
+    }
 
-        let response_body = self.client.get(&url).headers(headers).send()?.text()?;
+    /// Fetches the user the token belongs to, which also verifies the token
+    /// A rejected token is reported as `ApiError::Unauthorized`
+    pub fn fetch_user_info(&self) -> Result<User> {
+        let url = format!("{}/user", self.base_url);
+        self.get_json(&url)
+    }
 
-        let assignments = serde_json::from_str(&response_body);
-        match assignments {
-            Err(e) => {
-                println!("Response body: {:#?}", &response_body);
-                return Err(e.into());
-            }
-            Ok(assignments) => Ok(assignments),
-        }
+    pub fn fetch_assignments(&self) -> Result<Vec<Assignment>> {
+        let url = format!("{}/assignments", self.base_url);
+        self.get_collection(url)
     }
 
     pub fn fetch_subject(&self, subject_id: u64) -> Result<subject::Subject> {
-        let url = format!("{}/subjects/{}", BASE_URL, subject_id);
+        let url = format!("{}/subjects/{}", self.base_url, subject_id);
         let mut headers = HeaderMap::new();
         headers.insert(
             AUTHORIZATION,
@@ -101,8 +173,40 @@ impl WaniKaniClient {
             Ok(subject) => Ok(subject),
         }
     }
+
+    /// Fetches every subject, following pagination
+    /// Only subjects updated after `updated_after` are returned when it is set
+    pub fn fetch_subjects(
+        &self,
+        updated_after: Option<DateTime<Utc>>,
+    ) -> Result<Vec<subject::Subject>> {
+        let url = format!("{}/subjects", self.base_url);
+        let mut params: Vec<String> = self.levels_param().into_iter().collect();
+        if let Some(updated_after) = updated_after {
+            params.push(format!(
+                "updated_after={}",
+                updated_after.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ));
+        }
+
+        self.get_collection(with_query(url, &params))
+    }
+
+    /// Fetches several subjects at once by id, leaving out those above the subscription's levels
+    /// Long lists of ids are split over several requests to keep the urls short
+    pub fn fetch_subjects_by_ids(&self, subject_ids: &[u64]) -> Result<Vec<subject::Subject>> {
+        let mut subjects = Vec::new();
+        for ids in subject_ids.chunks(MAX_IDS_PER_REQUEST) {
+            let url = format!("{}/subjects", self.base_url);
+            let mut params = vec![format!("ids={}", join_ids(ids))];
+            params.extend(self.levels_param());
+            subjects.extend(self.get_collection(with_query(url, &params))?);
+        }
+        Ok(subjects)
+    }
+
     pub fn fetch_summary(&self) -> Result<summary::Summary> {
-        let url = format!("{}/summary", BASE_URL);
+        let url = format!("{}/summary", self.base_url);
         let mut headers = HeaderMap::new();
         headers.insert(
             AUTHORIZATION,
@@ -121,38 +225,188 @@ impl WaniKaniClient {
         }
     }
 
+    /// Fetches the assignments within the subscription's levels, following pagination
+    /// With `immediately_available` only those with a review due now are returned
     pub fn fetch_available_assignments(
         &self,
         immediately_available: bool,
-    ) -> Result<AssignmentCollection> {
-        let mut url = format!("{}/assignments", BASE_URL);
+    ) -> Result<Vec<Assignment>> {
+        let url = format!("{}/assignments", self.base_url);
+        let mut params: Vec<String> = self.levels_param().into_iter().collect();
+        if immediately_available {
+            params.push("immediately_available_for_review=true".to_string());
+        }
+
+        self.get_collection(with_query(url, &params))
+    }
+
+    /// Fetches every assignment matching the filters, such as `burned=true`, following pagination
+    /// Unless the filters name levels, only the levels the subscription grants are included
+    pub fn fetch_assignments_matching(&self, filters: &[String]) -> Result<Vec<Assignment>> {
+        let url = format!("{}/assignments", self.base_url);
+        let mut params = filters.to_vec();
+        if !filters.iter().any(|filter| filter.starts_with("levels=")) {
+            params.extend(self.levels_param());
+        }
+
+        self.get_collection(with_query(url, &params))
+    }
+
+    /// Fetches the user's progress through every level they reached
+    pub fn fetch_level_progressions(&self) -> Result<Vec<LevelProgression>> {
+        let url = format!("{}/level_progressions", self.base_url);
+        self.get_collection(url)
+    }
+
+    /// Fetches the resets the user made
+    pub fn fetch_resets(&self) -> Result<Vec<Reset>> {
+        let url = format!("{}/resets", self.base_url);
+        self.get_collection(url)
+    }
+
+    /// Fetches the SRS systems subjects can follow, WaniKani currently has two
+    pub fn fetch_spaced_repetition_systems(&self) -> Result<Vec<SpacedRepetitionSystem>> {
+        let url = format!("{}/spaced_repetition_systems", self.base_url);
+        self.get_collection(url)
+    }
+
+    /// Fetches the review statistics matching the given filters
+    pub fn fetch_review_statistics(
+        &self,
+        filters: &ReviewStatisticFilters,
+    ) -> Result<Vec<ReviewStatistic>> {
+        let url = format!("{}/review_statistics", self.base_url);
+        self.get_collection(with_query(url, &filters.params()))
+    }
+
+    /// Fetches the notes and synonyms the user made for the given subjects
+    /// Long lists of ids are split over several requests to keep the urls short
+    pub fn fetch_study_materials(&self, subject_ids: &[u64]) -> Result<Vec<StudyMaterial>> {
+        let mut study_materials = Vec::new();
+        for ids in subject_ids.chunks(MAX_IDS_PER_REQUEST) {
+            let url = format!("{}/study_materials", self.base_url);
+            let params = [format!("subject_ids={}", join_ids(ids))];
+            study_materials.extend(self.get_collection(with_query(url, &params))?);
+        }
+        Ok(study_materials)
+    }
+
+    /// Fetches the latest notes and synonyms of one subject, None if the user made none
+    pub fn fetch_study_material(&self, subject_id: u64) -> Result<Option<StudyMaterial>> {
+        Ok(self
+            .fetch_study_materials(&[subject_id])?
+            .into_iter()
+            .next())
+    }
+
+    pub fn create_study_material(
+        &self,
+        subject_id: u64,
+        update: &StudyMaterialUpdate,
+    ) -> Result<StudyMaterial> {
+        let url = format!("{}/study_materials", self.base_url);
+        let mut body = serde_json::to_value(update)?;
+        body["subject_id"] = subject_id.into();
+        let request = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "study_material": body }));
+        self.send_json(request)
+    }
+
+    pub fn update_study_material(
+        &self,
+        study_material_id: u64,
+        update: &StudyMaterialUpdate,
+    ) -> Result<StudyMaterial> {
+        let url = format!("{}/study_materials/{}", self.base_url, study_material_id);
+        let request = self
+            .client
+            .put(&url)
+            .json(&serde_json::json!({ "study_material": update }));
+        self.send_json(request)
+    }
+
+    /// Updates the study materials of a subject, creating them if the user has none yet
+    pub fn save_study_material(
+        &self,
+        subject_id: u64,
+        existing: Option<&StudyMaterial>,
+        update: &StudyMaterialUpdate,
+    ) -> Result<StudyMaterial> {
+        match existing {
+            Some(study_material) => self.update_study_material(study_material.id, update),
+            None => self.create_study_material(subject_id, update),
+        }
+    }
+
+    /// Submits a finished review, the returned review holds the SRS stage before and after
+    pub fn submit_review(&self, review_data: serde_json::Value) -> Result<Review> {
+        let url = format!("{}/reviews", self.base_url);
+        self.send_json(self.client.post(&url).json(&review_data))
+    }
+
+    // Add more methods for fetching reviews, lessons, etc.
+
+    fn auth_headers(&self) -> HeaderMap {
         let mut headers = HeaderMap::new();
         headers.insert(
             AUTHORIZATION,
             format!("Bearer {}", self.api_token).parse().unwrap(),
         );
+        headers
+    }
 
-        if immediately_available {
-            url.push_str("?immediately_available_for_review=true");
-        }
+    /// Sends a request with the authorization headers and parses the JSON response
+    fn send_json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let response = request.headers(self.auth_headers()).send()?;
+        check_status(response.status())?;
+        Ok(response.json()?)
+    }
 
-        let response_body = self.client.get(&url).headers(headers).send()?.text()?;
+    fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self.client.get(url).headers(self.auth_headers()).send()?;
+        check_status(response.status())?;
+        let response_body = response.text()?;
 
-        let assignments = serde_json::from_str(&response_body);
-        match assignments {
-            Err(e) => {
-                println!("Response body: {:#?}", &response_body);
-                return Err(e.into());
-            }
-            Ok(assignments) => Ok(assignments),
-        }
+        serde_json::from_str(&response_body).map_err(|e| {
+            anyhow!(
+                "Unexpected response from WaniKani ({}): {}",
+                e,
+                response_body
+            )
+        })
     }
 
-    pub fn submit_review(&self, review_data: serde_json::Value) -> Result<()> {
-        let url = format!("{}/reviews", BASE_URL);
-        self.client.post(&url).json(&review_data).send()?;
-        Ok(())
+    /// Fetches every page of a collection endpoint, following `pages.next_url`
+    fn get_collection<T: DeserializeOwned>(&self, url: String) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut next_url = Some(url);
+        while let Some(url) = next_url {
+            let page: Collection<T> = self.get_json(&url)?;
+            items.extend(page.data);
+            next_url = page.pages.next_url;
+        }
+        Ok(items)
     }
+}
 
-    // Add more methods for fetching reviews, lessons, etc.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_format() {
+        assert!(is_valid_token_format(
+            "01234567-89ab-cdef-0123-456789ABCDEF"
+        ));
+        assert!(!is_valid_token_format(
+            "01234567-89ab-cdef-0123-456789abcde"
+        ));
+        assert!(!is_valid_token_format("0123456789abcdef0123456789abcdef"));
+        assert!(!is_valid_token_format(
+            "g1234567-89ab-cdef-0123-456789abcdef"
+        ));
+        assert!(!is_valid_token_format(""));
+    }
 }
@@ -3,24 +3,6 @@ use crate::wanikani::decode::{from_rfc3339, from_rfc3339_option};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct AssignmentCollection {
-    pub object: String,
-    pub url: String,
-    pub pages: Pages,
-    pub total_count: u64,
-    #[serde(deserialize_with = "from_rfc3339")]
-    pub data_updated_at: DateTime<Utc>,
-    pub data: Vec<Assignment>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Pages {
-    pub per_page: u64,
-    pub next_url: Option<String>,
-    pub previous_url: Option<String>,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Assignment {
     pub id: u64,
@@ -0,0 +1,142 @@
+use crate::display;
+use crate::wanikani::cache::SubjectCache;
+use crate::wanikani::dictionary::{describe_subject, summary_line};
+use crate::wanikani::notes;
+use crate::wanikani::subject::subject::SubjectData;
+use crate::WaniKaniClient;
+use anyhow::Result;
+
+/// Navigates between a subject, its components, the subjects it is used in
+/// and visually similar kanji, keeping a back/forward history
+pub struct SubjectBrowser {
+    client: WaniKaniClient,
+    display_method: String,
+    colors: bool,
+    back: Vec<u64>,
+    forward: Vec<u64>,
+}
+
+impl SubjectBrowser {
+    pub fn new(client: WaniKaniClient, display_method: String, colors: bool) -> Self {
+        SubjectBrowser {
+            client,
+            display_method,
+            colors,
+            back: Vec::new(),
+            forward: Vec::new(),
+        }
+    }
+
+    pub fn browse(&mut self, cache: &mut SubjectCache, subject_id: u64) -> Result<()> {
+        self.back.clear();
+        self.forward.clear();
+        let mut current = subject_id;
+
+        loop {
+            cache.ensure(&self.client, &[current])?;
+            let subject = match cache.get(current) {
+                Some(subject) => subject,
+                None => {
+                    display::display_text(
+                        &self.display_method,
+                        "This subject is above the levels your subscription grants access to.",
+                    );
+                    match self.back.pop() {
+                        Some(previous) => {
+                            current = previous;
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+            };
+            let groups = related_groups(&subject.data);
+            let related_ids: Vec<u64> = groups
+                .iter()
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            cache.ensure(&self.client, &related_ids)?;
+
+            let mut text = describe_subject(cache.get(current).unwrap());
+            let mut number = 0;
+            for (label, ids) in &groups {
+                text.push_str(&format!("\n\n{}:", label));
+                for id in ids.iter() {
+                    number += 1;
+                    let line = cache
+                        .get(*id)
+                        .map(summary_line)
+                        .unwrap_or_else(|| format!("Subject {} (locked)", id));
+                    text.push_str(&format!("\n  {}. {}", number, line));
+                }
+            }
+            display::display_markup(&self.display_method, &text, self.colors);
+
+            let mut commands = vec!["a number to open a related subject"];
+            if !self.back.is_empty() {
+                commands.push("'b' to go back");
+            }
+            if !self.forward.is_empty() {
+                commands.push("'f' to go forward");
+            }
+            commands.push("'n' to edit your notes");
+            commands.push("'q' to close");
+            let command = display::text_input(
+                &self.display_method,
+                &format!("Enter {}:", commands.join(", ")),
+            );
+
+            match command.as_str() {
+                "q" => break,
+                "n" => {
+                    let subject = cache.get(current).unwrap().clone();
+                    notes::edit_notes(&self.display_method, &self.client, &subject)?;
+                }
+                "b" => {
+                    if let Some(previous) = self.back.pop() {
+                        self.forward.push(current);
+                        current = previous;
+                    }
+                }
+                "f" => {
+                    if let Some(next) = self.forward.pop() {
+                        self.back.push(current);
+                        current = next;
+                    }
+                }
+                _ => match command.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= related_ids.len() => {
+                        self.back.push(current);
+                        self.forward.clear();
+                        current = related_ids[n - 1];
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Related subjects of a subject, grouped under the headings WaniKani uses for them
+fn related_groups(data: &SubjectData) -> Vec<(&'static str, Vec<u64>)> {
+    let groups = match data {
+        SubjectData::Radical(_) => vec![("Found In Kanji", data.amalgamation_subject_ids())],
+        SubjectData::Kanji(_) => vec![
+            ("Radical Combination", data.component_subject_ids()),
+            (
+                "Visually Similar Kanji",
+                data.visually_similar_subject_ids(),
+            ),
+            ("Found In Vocabulary", data.amalgamation_subject_ids()),
+        ],
+        SubjectData::Vocabulary(_) => vec![("Kanji Composition", data.component_subject_ids())],
+        SubjectData::KanaVocabulary(_) => vec![],
+    };
+    groups
+        .into_iter()
+        .filter(|(_, ids)| !ids.is_empty())
+        .map(|(label, ids)| (label, ids.to_vec()))
+        .collect()
+}
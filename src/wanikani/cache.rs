@@ -0,0 +1,201 @@
+use crate::wanikani::api::WaniKaniClient;
+use crate::wanikani::review_statistic::{ReviewStatistic, ReviewStatisticFilters};
+use crate::wanikani::spaced_repetition_system::SpacedRepetitionSystem;
+use crate::wanikani::srs::SrsSystem;
+use crate::wanikani::store;
+use crate::wanikani::subject::subject::Subject;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+const SUBJECT_CACHE_FILE: &str = "subjects.json";
+const REVIEW_STATISTIC_CACHE_FILE: &str = "review_statistics.json";
+const SRS_SYSTEM_CACHE_FILE: &str = "spaced_repetition_systems.json";
+/// SRS systems almost never change, so they are only fetched again after this many days
+const SRS_SYSTEM_MAX_AGE_DAYS: i64 = 7;
+
+/// Local copy of the WaniKani subjects, kept on disk between runs
+#[derive(Serialize, Deserialize, Default)]
+pub struct SubjectCache {
+    #[serde(skip)]
+    path: PathBuf,
+    updated_at: Option<DateTime<Utc>>,
+    /// Subscription level limit the cache was filled with
+    #[serde(default)]
+    max_level_granted: Option<u8>,
+    subjects: BTreeMap<u64, Subject>,
+}
+
+impl SubjectCache {
+    /// Loads the cache stored in `cache_dir`, or an empty cache if there is none or it is unreadable
+    pub fn load(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join(SUBJECT_CACHE_FILE);
+        let mut cache: SubjectCache = store::load_cache(&path)?;
+        cache.path = path;
+        Ok(cache)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json(&self.path, self)
+    }
+
+    /// Fetches the subjects updated since the last refresh and stores them on disk
+    /// Returns the number of subjects that were added or updated
+    pub fn refresh(&mut self, client: &WaniKaniClient) -> Result<usize> {
+        if self.max_level_granted != client.max_level_granted() {
+            // The subscription changed, so levels may have been locked or unlocked
+            self.subjects.clear();
+            self.updated_at = None;
+            self.max_level_granted = client.max_level_granted();
+        }
+
+        let started_at = Utc::now();
+        let subjects = client.fetch_subjects(self.updated_at)?;
+        let updated = subjects.len();
+
+        for subject in subjects {
+            self.subjects.insert(subject.id, subject);
+        }
+        self.updated_at = Some(started_at);
+        self.save()?;
+        Ok(updated)
+    }
+
+    /// Makes sure the given subjects are cached, fetching the missing ones in a single request
+    pub fn ensure(&mut self, client: &WaniKaniClient, subject_ids: &[u64]) -> Result<()> {
+        let missing: Vec<u64> = subject_ids
+            .iter()
+            .copied()
+            .filter(|id| !self.subjects.contains_key(id))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        for subject in client.fetch_subjects_by_ids(&missing)? {
+            self.subjects.insert(subject.id, subject);
+        }
+        self.save()
+    }
+
+    pub fn get(&self, subject_id: u64) -> Option<&Subject> {
+        self.subjects.get(&subject_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subjects.is_empty()
+    }
+
+    pub fn subjects(&self) -> impl Iterator<Item = &Subject> {
+        self.subjects.values()
+    }
+}
+
+/// Local copy of the user's review statistics, kept on disk between runs
+#[derive(Serialize, Deserialize, Default)]
+pub struct ReviewStatisticCache {
+    #[serde(skip)]
+    path: PathBuf,
+    updated_at: Option<DateTime<Utc>>,
+    /// Keyed by subject id, there is one statistic per subject
+    statistics: BTreeMap<u64, ReviewStatistic>,
+}
+
+impl ReviewStatisticCache {
+    /// Loads the cache stored in `cache_dir`, or an empty cache if there is none or it is unreadable
+    pub fn load(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join(REVIEW_STATISTIC_CACHE_FILE);
+        let mut cache: ReviewStatisticCache = store::load_cache(&path)?;
+        cache.path = path;
+        Ok(cache)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json(&self.path, self)
+    }
+
+    /// Fetches the statistics updated since the last refresh and stores them on disk
+    /// Returns the number of statistics that were added or updated
+    pub fn refresh(&mut self, client: &WaniKaniClient) -> Result<usize> {
+        let started_at = Utc::now();
+        let statistics = client.fetch_review_statistics(&ReviewStatisticFilters {
+            updated_after: self.updated_at,
+            ..Default::default()
+        })?;
+        let updated = statistics.len();
+
+        for statistic in statistics {
+            self.statistics.insert(statistic.data.subject_id, statistic);
+        }
+        self.updated_at = Some(started_at);
+        self.save()?;
+        Ok(updated)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statistics.is_empty()
+    }
+
+    /// The cached statistics passing `filters`
+    pub fn matching<'a>(
+        &'a self,
+        filters: &'a ReviewStatisticFilters,
+    ) -> impl Iterator<Item = &'a ReviewStatistic> {
+        self.statistics
+            .values()
+            .filter(move |statistic| filters.matches(statistic))
+    }
+}
+
+/// Local copy of the SRS systems, kept on disk between runs
+#[derive(Serialize, Deserialize, Default)]
+pub struct SrsSystemCache {
+    #[serde(skip)]
+    path: PathBuf,
+    updated_at: Option<DateTime<Utc>>,
+    systems: BTreeMap<u64, SpacedRepetitionSystem>,
+}
+
+impl SrsSystemCache {
+    /// Loads the cache stored in `cache_dir`, or an empty cache if there is none or it is unreadable
+    pub fn load(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join(SRS_SYSTEM_CACHE_FILE);
+        let mut cache: SrsSystemCache = store::load_cache(&path)?;
+        cache.path = path;
+        Ok(cache)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json(&self.path, self)
+    }
+
+    /// Fetches the systems again when the cache is empty or older than a week
+    pub fn refresh_if_stale(&mut self, client: &WaniKaniClient) -> Result<()> {
+        let max_age = Duration::days(SRS_SYSTEM_MAX_AGE_DAYS);
+        if self
+            .updated_at
+            .is_some_and(|updated_at| Utc::now() - updated_at < max_age)
+        {
+            return Ok(());
+        }
+
+        let started_at = Utc::now();
+        self.systems = client
+            .fetch_spaced_repetition_systems()?
+            .into_iter()
+            .map(|system| (system.id, system))
+            .collect();
+        self.updated_at = Some(started_at);
+        self.save()
+    }
+
+    /// The cached systems by id, ready to compute stages with
+    pub fn systems(&self) -> HashMap<u64, SrsSystem> {
+        self.systems
+            .iter()
+            .map(|(id, system)| (*id, SrsSystem::new(&system.data)))
+            .collect()
+    }
+}
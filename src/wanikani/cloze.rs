@@ -0,0 +1,132 @@
+use crate::config::Settings;
+use crate::display;
+use crate::wanikani::cache::SubjectCache;
+use crate::wanikani::quiz::{accepted_readings, blank_sentence, learned_subject_ids};
+use crate::wanikani::subject::subject::{ContextSentence, Subject};
+use crate::wanikani::utils::utils::{play_audio, validate_reading};
+use crate::WaniKaniClient;
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use std::path::PathBuf;
+
+/// Fill-in-the-blank practice with the context sentences of learned vocabulary
+pub struct ClozeQuiz {
+    client: WaniKaniClient,
+    display_method: String,
+    cache_dir: PathBuf,
+    settings: Settings,
+}
+
+impl ClozeQuiz {
+    pub fn new(
+        client: WaniKaniClient,
+        display_method: String,
+        cache_dir: PathBuf,
+        settings: Settings,
+    ) -> Self {
+        ClozeQuiz {
+            client,
+            display_method,
+            cache_dir,
+            settings,
+        }
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        let subject_ids = learned_subject_ids(
+            &self.client,
+            "vocabulary,kana_vocabulary",
+            self.settings.review_session_size,
+        )?;
+        if subject_ids.is_empty() {
+            display::display_text(
+                &self.display_method,
+                "Finish some vocabulary lessons first, the quiz only uses words you have learned.",
+            );
+            return Ok(());
+        }
+        let mut cache = SubjectCache::load(&self.cache_dir)?;
+        cache.ensure(&self.client, &subject_ids)?;
+
+        let (mut correct, mut total) = (0, 0);
+        for subject_id in subject_ids {
+            let Some(subject) = cache.get(subject_id) else {
+                continue;
+            };
+            let Some(characters) = subject.data.characters() else {
+                continue;
+            };
+            let mut sentences: Vec<_> = subject
+                .data
+                .context_sentences()
+                .iter()
+                .filter_map(|s| blank_sentence(&s.ja, characters).map(|blanked| (blanked, s)))
+                .collect();
+            sentences.shuffle(&mut rand::thread_rng());
+            // Words without a usable sentence are skipped without counting them
+            let Some((blanked, sentence)) = sentences.pop() else {
+                continue;
+            };
+            match self.ask_sentence(subject, characters, &blanked, sentence) {
+                Some(true) => {
+                    correct += 1;
+                    total += 1;
+                }
+                Some(false) => total += 1,
+                None => break,
+            }
+        }
+
+        if total == 0 {
+            display::display_text(
+                &self.display_method,
+                "None of your words have a context sentence to practise with yet.",
+            );
+        } else {
+            display::display_text(
+                &self.display_method,
+                &format!("Quiz finished. {} of {} sentences correct.", correct, total),
+            );
+        }
+        Ok(())
+    }
+
+    /// Asks for the word blanked out of a context sentence
+    /// Returns whether the answer was correct, or `None` if the user quit
+    fn ask_sentence(
+        &self,
+        subject: &Subject,
+        characters: &str,
+        blanked: &str,
+        sentence: &ContextSentence,
+    ) -> Option<bool> {
+        display::display_text(&self.display_method, blanked);
+        let answer = display::text_input(
+            &self.display_method,
+            "Enter the missing word in its dictionary form (in hiragana or romaji), or 'q' to quit:",
+        );
+        if answer == "q" {
+            return None;
+        }
+
+        let readings = accepted_readings(&subject.data);
+        let correct = answer.trim() == characters || validate_reading(&answer, &readings);
+        if correct && self.settings.autoplay_audio {
+            if let Some(audio) = subject.data.pronunciation_audios().first() {
+                play_audio(&audio.url);
+            }
+        }
+        display::display_text(
+            &self.display_method,
+            &format!(
+                "{} The word is {} ({}).\n\n{}\n{}",
+                if correct { "Correct!" } else { "Incorrect." },
+                characters,
+                readings.join(", "),
+                sentence.ja,
+                sentence.en
+            ),
+        );
+        Some(correct)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::wanikani::decode::from_rfc3339_option;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A page of any of the collection endpoints, `pages.next_url` leads to the next one
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Collection<T> {
+    pub object: String,
+    pub url: String,
+    pub pages: Pages,
+    pub total_count: u64,
+    /// Not set when the collection is empty
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub data_updated_at: Option<DateTime<Utc>>,
+    pub data: Vec<T>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Pages {
+    pub per_page: u64,
+    pub next_url: Option<String>,
+    pub previous_url: Option<String>,
+}
@@ -0,0 +1,53 @@
+use crate::display;
+use crate::wanikani::user::UserData;
+use crate::WaniKaniClient;
+use anyhow::Result;
+use chrono::{Local, Utc};
+
+/// Shows the level, queue sizes and upcoming reviews above the main menu
+/// `user` is the one loaded when logging in, only the summary is fetched on every redraw
+pub fn show_dashboard(output_method: &str, client: &WaniKaniClient, user: &UserData) {
+    match dashboard_text(client, user) {
+        Ok(text) => display::display_text(output_method, &text),
+        Err(e) => display::display_text(
+            output_method,
+            &format!("Could not load the dashboard: {}", e),
+        ),
+    }
+}
+
+fn dashboard_text(client: &WaniKaniClient, user: &UserData) -> Result<String> {
+    // The queues are frozen during vacation mode, so counts and forecast would be misleading
+    if let Some(notice) = user.vacation_notice() {
+        return Ok(format!(
+            "\nLevel {}\n\n*** VACATION MODE ***\n{}",
+            user.level, notice
+        ));
+    }
+
+    let summary = client.fetch_summary()?;
+    let available_reviews = summary.get_available_reviews().len();
+    let mut lines = vec![format!(
+        "\nLevel {} | {} lessons | {} reviews",
+        user.level,
+        summary.get_available_lessons().len(),
+        available_reviews
+    )];
+
+    let forecast = summary.forecast(Utc::now());
+    if !forecast.is_empty() {
+        lines.push("Review forecast:".to_string());
+        let mut total = available_reviews;
+        for (available_at, count) in forecast {
+            total += count;
+            lines.push(format!(
+                "  {}  +{} ({})",
+                available_at.with_timezone(&Local).format("%a %H:%M"),
+                count,
+                total
+            ));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,385 @@
+use crate::display;
+use crate::wanikani::browser::SubjectBrowser;
+use crate::wanikani::cache::SubjectCache;
+use crate::wanikani::subject::subject::{Meaning, Subject, SubjectData};
+use crate::wanikani::utils::utils::{normalize_string, romaji_to_hiragana};
+use crate::WaniKaniClient;
+use anyhow::Result;
+use std::path::PathBuf;
+
+const MAX_RESULTS: usize = 20;
+
+pub struct DictionarySession {
+    client: WaniKaniClient,
+    display_method: String,
+    cache_dir: PathBuf,
+    colors: bool,
+}
+
+impl DictionarySession {
+    pub fn new(
+        client: WaniKaniClient,
+        display_method: String,
+        cache_dir: PathBuf,
+        colors: bool,
+    ) -> Self {
+        DictionarySession {
+            client,
+            display_method,
+            cache_dir,
+            colors,
+        }
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        let mut cache = SubjectCache::load(&self.cache_dir)?;
+        if cache.is_empty() {
+            display::display_text(
+                &self.display_method,
+                "Downloading subjects, this may take a moment...",
+            );
+        }
+        if let Err(e) = cache.refresh(&self.client) {
+            // Searching works offline as long as the subjects were downloaded before
+            if cache.is_empty() {
+                return Err(e);
+            }
+            display::display_text(
+                &self.display_method,
+                &format!(
+                    "Could not update the subjects, searching the saved ones: {}",
+                    e
+                ),
+            );
+        }
+
+        loop {
+            let query = display::text_input(
+                &self.display_method,
+                "Search by meaning, reading, characters or level ('q' to go back):",
+            );
+            if query == "q" {
+                break;
+            }
+            if query.is_empty() {
+                continue;
+            }
+
+            let results = search(cache.subjects(), &query);
+            if results.is_empty() {
+                display::display_text(
+                    &self.display_method,
+                    &format!("No subjects found for \"{}\".", query),
+                );
+                continue;
+            }
+
+            let mut listing = results
+                .iter()
+                .take(MAX_RESULTS)
+                .enumerate()
+                .map(|(i, subject)| format!("{}. {}", i + 1, summary_line(subject)))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if results.len() > MAX_RESULTS {
+                listing.push_str(&format!(
+                    "\n... and {} more, refine your search to see them",
+                    results.len() - MAX_RESULTS
+                ));
+            }
+            display::display_text(&self.display_method, &listing);
+
+            let choice = display::text_input(
+                &self.display_method,
+                "Enter a number to see details, or press Enter to search again:",
+            );
+            match choice.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= results.len().min(MAX_RESULTS) => {
+                    let subject_id = results[n - 1].id;
+                    SubjectBrowser::new(
+                        self.client.clone(),
+                        self.display_method.clone(),
+                        self.colors,
+                    )
+                    .browse(&mut cache, subject_id)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Searches subjects by level, characters, reading (kana or romaji) or meaning
+/// Exact matches come first, then prefix matches, then partial matches, each ordered by level
+pub fn search<'a>(subjects: impl Iterator<Item = &'a Subject>, query: &str) -> Vec<&'a Subject> {
+    let query = normalize_string(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<(u8, &Subject)> = subjects
+        .filter_map(|subject| match_rank(subject, &query).map(|rank| (rank, subject)))
+        .collect();
+    results.sort_by_key(|(rank, subject)| (*rank, subject.data.level(), subject.id));
+    results.into_iter().map(|(_, subject)| subject).collect()
+}
+
+/// Ranks how well a subject matches a normalized query, lower is better
+fn match_rank(subject: &Subject, query: &str) -> Option<u8> {
+    if let Some(level) = parse_level(query) {
+        return (subject.data.level() == level).then_some(0);
+    }
+
+    let mut ranks = Vec::new();
+    if query.is_ascii() {
+        for meaning in accepted_meanings(&subject.data) {
+            ranks.push(text_rank(&meaning.to_lowercase(), query));
+        }
+
+        let kana = romaji_to_hiragana(query);
+        if kana.chars().all(|c| !c.is_ascii()) {
+            ranks.push(kana_rank(&subject.data, &kana));
+        }
+    } else {
+        ranks.push(kana_rank(&subject.data, query));
+        if let Some(characters) = subject.data.characters() {
+            ranks.push(text_rank(characters, query));
+        }
+    }
+
+    ranks.into_iter().flatten().min()
+}
+
+fn kana_rank(data: &SubjectData, kana: &str) -> Option<u8> {
+    let mut ranks: Vec<Option<u8>> = data
+        .readings()
+        .iter()
+        .map(|r| prefix_rank(&r.reading, kana))
+        .collect();
+    if let SubjectData::KanaVocabulary(vocab) = data {
+        ranks.push(prefix_rank(&vocab.characters, kana));
+    }
+    ranks.into_iter().flatten().min()
+}
+
+fn prefix_rank(candidate: &str, query: &str) -> Option<u8> {
+    if candidate == query {
+        Some(0)
+    } else if candidate.starts_with(query) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+fn text_rank(candidate: &str, query: &str) -> Option<u8> {
+    prefix_rank(candidate, query).or_else(|| candidate.contains(query).then_some(2))
+}
+
+/// Parses queries such as "5", "level 5" or "level:5"
+fn parse_level(query: &str) -> Option<u8> {
+    let level = query
+        .strip_prefix("level")
+        .unwrap_or(query)
+        .trim_start_matches([':', ' '])
+        .parse::<u8>()
+        .ok()?;
+    (1..=60).contains(&level).then_some(level)
+}
+
+/// Meanings accepted as answers, including whitelisted auxiliary meanings
+fn accepted_meanings(data: &SubjectData) -> impl Iterator<Item = &String> {
+    data.meanings()
+        .iter()
+        .filter(|m| m.accepted_answer.unwrap_or(true))
+        .chain(
+            data.auxiliary_meanings()
+                .iter()
+                .filter(|m| m.type_.as_deref() == Some("whitelist")),
+        )
+        .map(|m| &m.meaning)
+}
+
+fn join_meanings(meanings: &[Meaning]) -> String {
+    meanings
+        .iter()
+        .map(|m| m.meaning.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// One line description used in result lists, e.g. "大 - Big (Kanji, level 1)"
+pub fn summary_line(subject: &Subject) -> String {
+    format!(
+        "{} - {} ({}, level {})",
+        subject.data.characters().unwrap_or(subject.data.slug()),
+        subject
+            .data
+            .meanings()
+            .first()
+            .map(|m| m.meaning.as_str())
+            .unwrap_or(""),
+        subject.data.type_name(),
+        subject.data.level()
+    )
+}
+
+/// Full description of a subject with its meanings, readings, mnemonics and context sentences
+pub fn describe_subject(subject: &Subject) -> String {
+    let data = &subject.data;
+    let mut lines = vec![
+        format!(
+            "{} ({}, level {})",
+            data.characters().unwrap_or(data.slug()),
+            data.type_name(),
+            data.level()
+        ),
+        format!("Meanings: {}", join_meanings(data.meanings())),
+    ];
+
+    let whitelisted: Vec<&String> = data
+        .auxiliary_meanings()
+        .iter()
+        .filter(|m| m.type_.as_deref() == Some("whitelist"))
+        .map(|m| &m.meaning)
+        .collect();
+    if !whitelisted.is_empty() {
+        lines.push(format!(
+            "Also accepted: {}",
+            whitelisted
+                .iter()
+                .map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    match data {
+        SubjectData::Kanji(_) => {
+            for (type_, label) in [
+                ("onyomi", "On'yomi"),
+                ("kunyomi", "Kun'yomi"),
+                ("nanori", "Nanori"),
+            ] {
+                let readings: Vec<&str> = data
+                    .readings_of_type(type_)
+                    .iter()
+                    .map(|r| r.reading.as_str())
+                    .collect();
+                if !readings.is_empty() {
+                    lines.push(format!("{}: {}", label, readings.join(", ")));
+                }
+            }
+        }
+        SubjectData::Vocabulary(vocab) => {
+            lines.push(format!(
+                "Readings: {}",
+                vocab
+                    .readings
+                    .iter()
+                    .map(|r| r.reading.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        SubjectData::Radical(_) | SubjectData::KanaVocabulary(_) => {}
+    }
+
+    if !data.parts_of_speech().is_empty() {
+        lines.push(format!(
+            "Parts of speech: {}",
+            data.parts_of_speech().join(", ")
+        ));
+    }
+
+    lines.push(format!("\nMeaning Mnemonic: {}", data.meaning_mnemonic()));
+    if let Some(reading_mnemonic) = data.reading_mnemonic() {
+        lines.push(format!("Reading Mnemonic: {}", reading_mnemonic));
+    }
+
+    if !data.context_sentences().is_empty() {
+        lines.push("\nContext Sentences:".to_string());
+        for sentence in data.context_sentences() {
+            lines.push(format!("  {}\n  {}", sentence.ja, sentence.en));
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wanikani::subject::subject::{KanjiData, Reading};
+    use chrono::{TimeZone, Utc};
+
+    fn kanji(id: u64, characters: &str, level: u8, meaning: &str, reading: &str) -> Subject {
+        let created_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let to_meaning = |meaning: &str, primary: Option<bool>, type_: Option<&str>| Meaning {
+            meaning: meaning.to_string(),
+            primary,
+            accepted_answer: primary,
+            type_: type_.map(str::to_string),
+        };
+        Subject {
+            id,
+            url: String::new(),
+            data_updated_at: created_at,
+            data: SubjectData::Kanji(KanjiData {
+                amalgamation_subject_ids: Vec::new(),
+                auxiliary_meanings: vec![to_meaning("huge", None, Some("whitelist"))],
+                characters: characters.to_string(),
+                component_subject_ids: Vec::new(),
+                created_at,
+                document_url: String::new(),
+                hidden_at: None,
+                lesson_position: 0,
+                level,
+                meanings: vec![to_meaning(meaning, Some(true), None)],
+                meaning_hint: None,
+                meaning_mnemonic: String::new(),
+                readings: vec![Reading {
+                    reading: reading.to_string(),
+                    primary: true,
+                    accepted_answer: true,
+                    type_: Some("onyomi".to_string()),
+                }],
+                reading_mnemonic: String::new(),
+                reading_hint: None,
+                slug: characters.to_string(),
+                visually_similar_subject_ids: Vec::new(),
+                spaced_repetition_system_id: 1,
+            }),
+        }
+    }
+
+    fn ids(results: Vec<&Subject>) -> Vec<u64> {
+        results.iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn test_search_by_meaning_and_level() {
+        let subjects = [
+            kanji(1, "大", 1, "Big", "だい"),
+            kanji(2, "犬", 2, "Dog", "けん"),
+            kanji(3, "太", 2, "Fat Big", "たい"),
+        ];
+        assert_eq!(ids(search(subjects.iter(), "big")), vec![1, 3]);
+        assert_eq!(ids(search(subjects.iter(), "huge")), vec![1, 2, 3]);
+        assert_eq!(ids(search(subjects.iter(), "level 2")), vec![2, 3]);
+        assert!(search(subjects.iter(), "  ").is_empty());
+    }
+
+    #[test]
+    fn test_search_by_reading_and_characters() {
+        let subjects = [
+            kanji(1, "大", 1, "Big", "だい"),
+            kanji(2, "犬", 2, "Dog", "けん"),
+        ];
+        assert_eq!(ids(search(subjects.iter(), "ken")), vec![2]);
+        assert_eq!(ids(search(subjects.iter(), "だい")), vec![1]);
+        assert_eq!(ids(search(subjects.iter(), "犬")), vec![2]);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::config::Settings;
+use crate::display;
+use crate::wanikani::api::join_ids;
+use crate::wanikani::history::History;
+use crate::wanikani::reviews::{order_assignments, ReviewSession};
+use crate::wanikani::session_summary::SessionSummary;
+use crate::WaniKaniClient;
+use anyhow::Result;
+use chrono::{Duration, SecondsFormat, Utc};
+use std::path::Path;
+
+/// How far back recent mistakes and recent lessons go
+const RECENT_HOURS: i64 = 24;
+
+const SUBJECT_TYPES: &[&str] = &["radical", "kanji", "vocabulary", "kana_vocabulary"];
+
+/// Asks which items to study and quizzes them without submitting anything to WaniKani
+/// Returns `None` if the user went back or there was nothing to study
+pub fn start_extra_study(
+    output_method: &str,
+    client: &WaniKaniClient,
+    settings: &Settings,
+    cache_dir: &Path,
+    data_dir: &Path,
+) -> Result<Option<SessionSummary>> {
+    display::display_text(
+        output_method,
+        "Extra Study:\n1. Recent mistakes (last 24 hours, only reviews done in kanikani count)\n2. Recent lessons (last 24 hours)\n3. Burned items\n4. Custom selection by level and type",
+    );
+    let choice = display::text_input(output_method, "Choose what to study, or 'q' to go back:");
+
+    let since = Utc::now() - Duration::hours(RECENT_HOURS);
+    let mut assignments = match choice.as_str() {
+        "1" => {
+            let subject_ids = History::load(data_dir)?.recent_mistakes(since);
+            if subject_ids.is_empty() {
+                Vec::new()
+            } else {
+                client.fetch_assignments_matching(&[format!(
+                    "subject_ids={}",
+                    join_ids(&subject_ids)
+                )])?
+            }
+        }
+        "2" => client
+            .fetch_assignments_matching(&[
+                "started=true".to_string(),
+                format!(
+                    "updated_after={}",
+                    since.to_rfc3339_opts(SecondsFormat::Secs, true)
+                ),
+            ])?
+            .into_iter()
+            .filter(|a| a.data.started_at.is_some_and(|started| started >= since))
+            .collect(),
+        "3" => client.fetch_assignments_matching(&["burned=true".to_string()])?,
+        "4" => match custom_filters(output_method, client) {
+            Some(filters) => client.fetch_assignments_matching(&filters)?,
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    if assignments.is_empty() {
+        display::display_text(output_method, "There is nothing to study here yet.");
+        return Ok(None);
+    }
+
+    order_assignments(&mut assignments, settings.review_order);
+    let mut session = ReviewSession::new(
+        client.clone(),
+        &assignments,
+        output_method.to_string(),
+        cache_dir.to_path_buf(),
+        settings.clone(),
+    )
+    .extra_study()
+    .record_history(History::load(data_dir)?);
+    session.start().map(Some)
+}
+
+/// Asks for levels and subject types, returns the assignment filters or `None` if the input is invalid
+fn custom_filters(output_method: &str, client: &WaniKaniClient) -> Option<Vec<String>> {
+    let mut filters = vec!["started=true".to_string()];
+
+    let input = display::text_input(
+        output_method,
+        "Levels to study, e.g. 1-5 or 3,7 (press Enter for all levels):",
+    );
+    if !input.is_empty() {
+        let levels: Vec<u8> = match parse_levels(&input) {
+            Ok(levels) => levels
+                .into_iter()
+                .filter(|level| client.is_level_granted(*level))
+                .collect(),
+            Err(e) => {
+                display::display_text(output_method, &e);
+                return None;
+            }
+        };
+        if levels.is_empty() {
+            display::display_text(
+                output_method,
+                "Your subscription does not include any of these levels.",
+            );
+            return None;
+        }
+        let levels = levels
+            .iter()
+            .map(|level| level.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        filters.push(format!("levels={}", levels));
+    }
+
+    let input = display::text_input(
+        output_method,
+        &format!(
+            "Subject types separated by commas: {} (press Enter for all types):",
+            SUBJECT_TYPES.join(", ")
+        ),
+    );
+    if !input.is_empty() {
+        let types: Vec<String> = input
+            .split(',')
+            .map(|type_| type_.trim().to_lowercase().replace(' ', "_"))
+            .collect();
+        if let Some(unknown) = types.iter().find(|t| !SUBJECT_TYPES.contains(&t.as_str())) {
+            display::display_text(output_method, &format!("Unknown subject type: {}", unknown));
+            return None;
+        }
+        filters.push(format!("subject_types={}", types.join(",")));
+    }
+
+    Some(filters)
+}
+
+/// Parses level selections such as "5", "1-5" or "1-3,7"
+pub fn parse_levels(input: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid levels: {}, expected e.g. 1-5 or 3,7", input);
+    let parse_level = |level: &str| match level.trim().parse::<u8>() {
+        Ok(level) if (1..=60).contains(&level) => Ok(level),
+        _ => Err(invalid()),
+    };
+
+    let mut levels = Vec::new();
+    for part in input.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_level(start)?, parse_level(end)?);
+                if start > end {
+                    return Err(invalid());
+                }
+                levels.extend(start..=end);
+            }
+            None => levels.push(parse_level(part)?),
+        }
+    }
+    levels.sort_unstable();
+    levels.dedup();
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_levels() {
+        assert_eq!(parse_levels("5"), Ok(vec![5]));
+        assert_eq!(parse_levels("1-3, 7,2"), Ok(vec![1, 2, 3, 7]));
+        assert!(parse_levels("0").is_err());
+        assert!(parse_levels("5-1").is_err());
+        assert!(parse_levels("one").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::wanikani::session_summary::{ItemResult, SessionKind};
+use crate::wanikani::store;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const HISTORY_FILE: &str = "history.json";
+
+/// Answers older than this are dropped from the history
+const HISTORY_DAYS: i64 = 90;
+
+/// One finished item of a review or extra study session
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub subject_id: u64,
+    pub answered_at: DateTime<Utc>,
+    pub incorrect_meaning_answers: u32,
+    pub incorrect_reading_answers: u32,
+    /// Extra study answers are only kept here, they are never submitted to WaniKani
+    pub extra_study: bool,
+}
+
+impl HistoryEntry {
+    pub fn is_correct(&self) -> bool {
+        self.incorrect_meaning_answers == 0 && self.incorrect_reading_answers == 0
+    }
+}
+
+/// Local record of recent answers, kept on disk between runs
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    #[serde(skip)]
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Loads the history stored in `data_dir`, or an empty history if there is none yet
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(HISTORY_FILE);
+        let mut history: History = store::load_json(&path)?.unwrap_or_default();
+        history.path = path;
+        Ok(history)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json(&self.path, self)
+    }
+
+    /// Adds a finished item of a review or extra study session and drops old answers
+    pub fn record(&mut self, kind: SessionKind, item: &ItemResult) {
+        let extra_study = match kind {
+            SessionKind::Reviews => false,
+            SessionKind::ExtraStudy => true,
+            SessionKind::Lessons => return,
+        };
+        self.entries.push(HistoryEntry {
+            subject_id: item.subject_id,
+            answered_at: item.answered_at,
+            incorrect_meaning_answers: item.incorrect_meaning_answers,
+            incorrect_reading_answers: item.incorrect_reading_answers,
+            extra_study,
+        });
+
+        let cutoff = Utc::now() - Duration::days(HISTORY_DAYS);
+        self.entries.retain(|entry| entry.answered_at >= cutoff);
+    }
+
+    /// How many review answers were wrong per subject since `since`
+    pub fn mistake_counts(&self, since: DateTime<Utc>) -> HashMap<u64, u32> {
+        let mut counts = HashMap::new();
+        for entry in &self.entries {
+            if entry.answered_at >= since && !entry.extra_study {
+                let mistakes = entry.incorrect_meaning_answers + entry.incorrect_reading_answers;
+                if mistakes > 0 {
+                    *counts.entry(entry.subject_id).or_insert(0) += mistakes;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Subjects answered incorrectly in reviews since `since`, most recent first
+    pub fn recent_mistakes(&self, since: DateTime<Utc>) -> Vec<u64> {
+        let mut subject_ids = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if entry.answered_at >= since
+                && !entry.extra_study
+                && !entry.is_correct()
+                && !subject_ids.contains(&entry.subject_id)
+            {
+                subject_ids.push(entry.subject_id);
+            }
+        }
+        subject_ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(subject_id: u64, hours_ago: i64, incorrect: u32, extra_study: bool) -> HistoryEntry {
+        HistoryEntry {
+            subject_id,
+            answered_at: Utc::now() - Duration::hours(hours_ago),
+            incorrect_meaning_answers: incorrect,
+            incorrect_reading_answers: 0,
+            extra_study,
+        }
+    }
+
+    #[test]
+    fn test_recent_mistakes() {
+        let history = History {
+            path: PathBuf::new(),
+            entries: vec![
+                entry(1, 30, 1, false),
+                entry(2, 10, 1, false),
+                entry(3, 5, 0, false),
+                entry(4, 3, 2, true),
+                entry(2, 2, 1, false),
+                entry(5, 1, 1, false),
+            ],
+        };
+        let since = Utc::now() - Duration::hours(24);
+        assert_eq!(history.recent_mistakes(since), vec![5, 2]);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::display;
+use crate::wanikani::store;
+use crate::wanikani::utils::utils::{hiragana_to_katakana, kana_table, romaji_to_hiragana};
+use anyhow::Result;
+use rand::distributions::{Distribution, WeightedIndex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const KANA_STATS_FILE: &str = "kana.json";
+
+/// How many of the weakest characters are listed at the end of a drill
+const WEAKEST_SHOWN: usize = 5;
+
+const DAKUTEN: &str = "がぎぐげござじずぜぞだぢづでどばびぶべぼぱぴぷぺぽ";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KanaSets {
+    pub hiragana: bool,
+    pub katakana: bool,
+    /// Include voiced sounds like が and ぱ
+    pub dakuten: bool,
+    /// Include contracted sounds like きゃ
+    pub yoon: bool,
+}
+
+impl KanaSets {
+    /// Parses a selection like "1,3", see the listing in `start_kana_trainer`
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut sets = KanaSets {
+            hiragana: false,
+            katakana: false,
+            dakuten: false,
+            yoon: false,
+        };
+        for choice in input.split(',').map(str::trim) {
+            match choice {
+                "1" => sets.hiragana = true,
+                "2" => sets.katakana = true,
+                "3" => sets.dakuten = true,
+                "4" => sets.yoon = true,
+                _ => return Err(format!("Invalid choice: {}", choice)),
+            }
+        }
+        // Dakuten and yōon alone still need a script to be drilled in
+        if !sets.hiragana && !sets.katakana {
+            sets.hiragana = true;
+        }
+        Ok(sets)
+    }
+
+    /// The kana to drill as (kana, romaji) pairs
+    pub fn characters(&self) -> Vec<(String, &'static str)> {
+        let hiragana: Vec<(&str, &str)> = kana_table()
+            .into_iter()
+            .map(|(romaji, kana)| (kana, romaji))
+            .filter(|(kana, _)| self.yoon || kana.chars().count() == 1)
+            .filter(|(kana, _)| self.dakuten || !kana.chars().any(|c| DAKUTEN.contains(c)))
+            .collect();
+
+        let mut characters = Vec::new();
+        if self.hiragana {
+            characters.extend(
+                hiragana
+                    .iter()
+                    .map(|(kana, romaji)| (kana.to_string(), *romaji)),
+            );
+        }
+        if self.katakana {
+            characters.extend(
+                hiragana
+                    .iter()
+                    .map(|(kana, romaji)| (hiragana_to_katakana(kana), *romaji)),
+            );
+        }
+        characters
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct KanaScore {
+    pub correct: u32,
+    pub incorrect: u32,
+}
+
+impl KanaScore {
+    pub fn accuracy(&self) -> Option<f64> {
+        let total = self.correct + self.incorrect;
+        (total > 0).then(|| self.correct as f64 / total as f64)
+    }
+
+    /// How often the character should come up, unseen characters and frequent misses come up most
+    pub fn weight(&self) -> f64 {
+        let miss_rate =
+            (self.incorrect as f64 + 1.0) / ((self.correct + self.incorrect) as f64 + 2.0);
+        1.0 + 4.0 * miss_rate
+    }
+}
+
+/// Per-character accuracy of the kana trainer, kept on disk between runs
+#[derive(Serialize, Deserialize, Default)]
+pub struct KanaStats {
+    #[serde(skip)]
+    path: PathBuf,
+    characters: BTreeMap<String, KanaScore>,
+}
+
+impl KanaStats {
+    /// Loads the stats stored in `data_dir`, or empty stats if there are none yet
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(KANA_STATS_FILE);
+        let mut stats: KanaStats = store::load_json(&path)?.unwrap_or_default();
+        stats.path = path;
+        Ok(stats)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json(&self.path, self)
+    }
+
+    pub fn score(&self, kana: &str) -> KanaScore {
+        self.characters.get(kana).copied().unwrap_or_default()
+    }
+
+    pub fn record(&mut self, kana: &str, correct: bool) {
+        let score = self.characters.entry(kana.to_string()).or_default();
+        if correct {
+            score.correct += 1;
+        } else {
+            score.incorrect += 1;
+        }
+    }
+
+    /// The characters with the lowest accuracy among `kana`, worst first
+    pub fn weakest<'a>(
+        &self,
+        kana: impl Iterator<Item = &'a str>,
+        count: usize,
+    ) -> Vec<(&'a str, f64)> {
+        let mut scored: Vec<(&str, f64)> = kana
+            .filter_map(|kana| Some((kana, self.score(kana).accuracy()?)))
+            .filter(|(_, accuracy)| *accuracy < 1.0)
+            .collect();
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scored.truncate(count);
+        scored
+    }
+}
+
+/// Whether `answer` is a romaji spelling of `kana`
+pub fn check_answer(answer: &str, kana: &str, romaji: &str) -> bool {
+    let answer = answer.trim().to_lowercase();
+    answer == romaji
+        || hiragana_to_katakana(&romaji_to_hiragana(&answer)) == hiragana_to_katakana(kana)
+}
+
+/// Drills kana chosen at random, weighted towards the characters missed most
+pub fn start_kana_trainer(output_method: &str, data_dir: &Path) -> Result<()> {
+    display::display_text(
+        output_method,
+        "Kana Trainer:\n1. Hiragana\n2. Katakana\n3. Dakuten (が, ぱ, ...)\n4. Yōon (きゃ, しゅ, ...)",
+    );
+    let input = display::text_input(
+        output_method,
+        "Choose the sets to drill separated by commas, e.g. 1,3, or 'q' to go back:",
+    );
+    if input == "q" || input.is_empty() {
+        return Ok(());
+    }
+    let sets = match KanaSets::parse(&input) {
+        Ok(sets) => sets,
+        Err(e) => {
+            display::display_text(output_method, &e);
+            return Ok(());
+        }
+    };
+
+    let characters = sets.characters();
+    let mut stats = KanaStats::load(data_dir)?;
+    let mut rng = rand::thread_rng();
+    let (mut correct, mut total) = (0, 0);
+    let mut previous = None;
+
+    loop {
+        let weights: Vec<f64> = characters
+            .iter()
+            .enumerate()
+            .map(|(i, (kana, _))| {
+                // Never ask the same character twice in a row
+                if previous == Some(i) {
+                    0.0
+                } else {
+                    stats.score(kana).weight()
+                }
+            })
+            .collect();
+        let index = WeightedIndex::new(&weights)?.sample(&mut rng);
+        let (kana, romaji) = &characters[index];
+        previous = Some(index);
+
+        let answer = display::text_input(
+            output_method,
+            &format!("{}\nEnter the romaji, or 'q' to finish:", kana),
+        );
+        if answer == "q" {
+            break;
+        }
+        let is_correct = check_answer(&answer, kana, romaji);
+        stats.record(kana, is_correct);
+        total += 1;
+        if is_correct {
+            correct += 1;
+        } else {
+            display::display_text(
+                output_method,
+                &format!("Incorrect, {} is {}.", kana, romaji),
+            );
+        }
+    }
+    stats.save()?;
+
+    let mut result = format!("Drill finished. {} of {} correct.", correct, total);
+    let weakest = stats.weakest(
+        characters.iter().map(|(kana, _)| kana.as_str()),
+        WEAKEST_SHOWN,
+    );
+    if !weakest.is_empty() {
+        result.push_str("\nCharacters to practise:");
+        for (kana, accuracy) in weakest {
+            result.push_str(&format!("\n  {} {:.0}%", kana, accuracy * 100.0));
+        }
+    }
+    display::display_text(output_method, &result);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kana_sets() {
+        let basic = KanaSets::parse("1").unwrap().characters();
+        assert_eq!(basic.len(), 46);
+        assert!(basic.contains(&("し".to_string(), "shi")));
+
+        let sets = KanaSets::parse("2, 3, 4").unwrap();
+        assert!(!sets.hiragana);
+        let characters = sets.characters();
+        assert!(characters.contains(&("ギョ".to_string(), "gyo")));
+        assert!(!characters.iter().any(|(kana, _)| kana == "か"));
+
+        assert!(KanaSets::parse("5").is_err());
+    }
+
+    #[test]
+    fn test_check_answer() {
+        assert!(check_answer("shi", "し", "shi"));
+        assert!(check_answer(" KA ", "カ", "ka"));
+        assert!(!check_answer("sa", "し", "shi"));
+    }
+
+    #[test]
+    fn test_weight() {
+        let unseen = KanaScore::default();
+        let known = KanaScore {
+            correct: 10,
+            incorrect: 0,
+        };
+        let missed = KanaScore {
+            correct: 1,
+            incorrect: 5,
+        };
+        assert!(known.weight() < unseen.weight());
+        assert!(unseen.weight() < missed.weight());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::config::Settings;
+use crate::display;
+use crate::wanikani::api::join_ids;
+use crate::wanikani::cache::{ReviewStatisticCache, SubjectCache};
+use crate::wanikani::history::History;
+use crate::wanikani::review_statistic::{ReviewStatisticData, ReviewStatisticFilters};
+use crate::wanikani::reviews::ReviewSession;
+use crate::wanikani::session_summary::SessionSummary;
+use crate::WaniKaniClient;
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Subjects scoring at least this much are considered leeches
+const LEECH_THRESHOLD: f64 = 1.0;
+
+/// How many leeches are listed and drilled at most
+const MAX_LEECHES: usize = 20;
+
+/// How far back local mistakes add to the score
+const RECENT_DAYS: i64 = 30;
+
+/// Extra score for each wrong answer in the local history
+const RECENT_MISTAKE_SCORE: f64 = 0.5;
+
+/// How much trouble a subject is causing
+/// Wrong answers count more the shorter the current streak of correct answers is,
+/// the worse of meaning and reading is used and recent local mistakes are added on top
+pub fn leech_score(stat: &ReviewStatisticData, recent_mistakes: u32) -> f64 {
+    let score = |incorrect: u32, streak: u32| incorrect as f64 / (streak.max(1) as f64).powf(1.5);
+    let meaning = score(stat.meaning_incorrect, stat.meaning_current_streak);
+    let reading = score(stat.reading_incorrect, stat.reading_current_streak);
+    meaning.max(reading) + recent_mistakes as f64 * RECENT_MISTAKE_SCORE
+}
+
+/// The subjects scoring as leeches, worst first
+pub fn rank_leeches<'a>(
+    stats: &'a [ReviewStatisticData],
+    recent_mistakes: &HashMap<u64, u32>,
+) -> Vec<(&'a ReviewStatisticData, f64)> {
+    let mut leeches: Vec<(&ReviewStatisticData, f64)> = stats
+        .iter()
+        .filter(|stat| !stat.hidden)
+        .map(|stat| {
+            let mistakes = recent_mistakes.get(&stat.subject_id).copied().unwrap_or(0);
+            (stat, leech_score(stat, mistakes))
+        })
+        .filter(|(_, score)| *score >= LEECH_THRESHOLD)
+        .collect();
+    leeches.sort_by(|a, b| b.1.total_cmp(&a.1));
+    leeches.truncate(MAX_LEECHES);
+    leeches
+}
+
+/// Lists the worst leeches and offers to drill them without affecting their SRS stage
+/// Returns the summary of the drill if there was one
+pub fn show_leeches(
+    output_method: &str,
+    client: &WaniKaniClient,
+    settings: &Settings,
+    cache_dir: &Path,
+    data_dir: &Path,
+) -> Result<Option<SessionSummary>> {
+    let mut statistics = ReviewStatisticCache::load(cache_dir)?;
+    if let Err(e) = statistics.refresh(client) {
+        if statistics.is_empty() {
+            return Err(e);
+        }
+        display::display_text(
+            output_method,
+            &format!(
+                "Could not update the statistics, ranking the saved ones: {}",
+                e
+            ),
+        );
+    }
+    let stats: Vec<ReviewStatisticData> = statistics
+        .matching(&ReviewStatisticFilters {
+            hidden: Some(false),
+            ..Default::default()
+        })
+        .map(|stat| stat.data.clone())
+        .collect();
+    let since = Utc::now() - Duration::days(RECENT_DAYS);
+    let recent_mistakes = History::load(data_dir)?.mistake_counts(since);
+    let leeches = rank_leeches(&stats, &recent_mistakes);
+    if leeches.is_empty() {
+        display::display_text(output_method, "You have no leeches, well done!");
+        return Ok(None);
+    }
+
+    let subject_ids: Vec<u64> = leeches.iter().map(|(stat, _)| stat.subject_id).collect();
+    let mut cache = SubjectCache::load(cache_dir)?;
+    cache.ensure(client, &subject_ids)?;
+
+    let mut listing = vec![format!("Your worst {} leeches:", leeches.len())];
+    for (i, (stat, score)) in leeches.iter().enumerate() {
+        let label = cache
+            .get(stat.subject_id)
+            .map(|subject| {
+                let characters = subject.data.characters().unwrap_or(subject.data.slug());
+                let meaning = subject
+                    .data
+                    .meanings()
+                    .first()
+                    .map(|m| m.meaning.as_str())
+                    .unwrap_or_default();
+                format!("{} ({})", characters, meaning)
+            })
+            .unwrap_or_else(|| stat.subject_id.to_string());
+        let mut line = format!(
+            "{}. {} - score {:.1}, meaning {} wrong (streak {})",
+            i + 1,
+            label,
+            score,
+            stat.meaning_incorrect,
+            stat.meaning_current_streak
+        );
+        if stat.subject_type != "radical" {
+            line.push_str(&format!(
+                ", reading {} wrong (streak {})",
+                stat.reading_incorrect, stat.reading_current_streak
+            ));
+        }
+        listing.push(line);
+    }
+    display::display_text(output_method, &listing.join("\n"));
+
+    let answer = display::text_input(
+        output_method,
+        "Enter 'd' to drill these leeches without affecting their SRS stage, or press Enter to go back:",
+    );
+    if answer != "d" {
+        return Ok(None);
+    }
+
+    let mut assignments =
+        client.fetch_assignments_matching(&[format!("subject_ids={}", join_ids(&subject_ids))])?;
+    // Worst leeches first
+    assignments.sort_by_key(|assignment| {
+        subject_ids
+            .iter()
+            .position(|id| *id == assignment.data.subject_id)
+    });
+    let mut session = ReviewSession::new(
+        client.clone(),
+        &assignments,
+        output_method.to_string(),
+        cache_dir.to_path_buf(),
+        settings.clone(),
+    )
+    .extra_study()
+    .record_history(History::load(data_dir)?);
+    session.start().map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(subject_id: u64, meaning: (u32, u32), reading: (u32, u32)) -> ReviewStatisticData {
+        ReviewStatisticData {
+            meaning_incorrect: meaning.0,
+            meaning_current_streak: meaning.1,
+            reading_incorrect: reading.0,
+            reading_current_streak: reading.1,
+            ..ReviewStatisticData::sample(subject_id)
+        }
+    }
+
+    #[test]
+    fn test_leech_score() {
+        assert_eq!(leech_score(&stat(1, (4, 1), (0, 1)), 0), 4.0);
+        assert_eq!(leech_score(&stat(1, (0, 1), (8, 4)), 0), 1.0);
+        assert_eq!(leech_score(&stat(1, (1, 0), (0, 3)), 2), 2.0);
+    }
+
+    #[test]
+    fn test_rank_leeches() {
+        let stats = vec![
+            stat(1, (2, 1), (0, 1)),
+            stat(2, (1, 9), (0, 9)),
+            stat(3, (6, 1), (0, 1)),
+        ];
+        let recent_mistakes = HashMap::from([(2, 2)]);
+        let ranked: Vec<(u64, f64)> = rank_leeches(&stats, &recent_mistakes)
+            .into_iter()
+            .map(|(stat, score)| (stat.subject_id, score))
+            .collect();
+        assert_eq!(ranked[0], (3, 6.0));
+        assert_eq!(ranked[1], (1, 2.0));
+        assert_eq!(ranked[2].0, 2);
+    }
+}
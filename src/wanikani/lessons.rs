@@ -1,31 +1,197 @@
+use crate::config::Settings;
 use crate::display;
-use crate::wanikani::subject::subject;
+use crate::wanikani::browser::SubjectBrowser;
+use crate::wanikani::cache::SubjectCache;
+use crate::wanikani::dictionary::summary_line;
+use crate::wanikani::notes;
+use crate::wanikani::session_summary::{ItemResult, SessionKind, SessionSummary};
+use crate::wanikani::subject::subject::{Meaning, Reading, Subject, SubjectData};
 use crate::wanikani::utils;
 use crate::WaniKaniClient;
 use anyhow::Result;
-use okanimoji::{generate_ascii_image, generate_ascii_text};
+use std::path::PathBuf;
+
+/// Pages of a lesson, mirroring the tabs of the official lessons
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LessonTab {
+    Composition,
+    Meaning,
+    Reading,
+    Context,
+}
+
+impl LessonTab {
+    pub fn title(&self) -> &'static str {
+        match self {
+            LessonTab::Composition => "Composition",
+            LessonTab::Meaning => "Meaning",
+            LessonTab::Reading => "Reading",
+            LessonTab::Context => "Context",
+        }
+    }
+}
+
+/// Tabs shown for a subject, in the order the official lessons show them
+pub fn lesson_tabs(data: &SubjectData) -> Vec<LessonTab> {
+    match data {
+        SubjectData::Radical(_) => vec![LessonTab::Meaning],
+        SubjectData::Kanji(_) => vec![
+            LessonTab::Composition,
+            LessonTab::Meaning,
+            LessonTab::Reading,
+        ],
+        SubjectData::Vocabulary(_) => vec![
+            LessonTab::Composition,
+            LessonTab::Meaning,
+            LessonTab::Reading,
+            LessonTab::Context,
+        ],
+        SubjectData::KanaVocabulary(_) => vec![LessonTab::Meaning, LessonTab::Context],
+    }
+}
+
+/// Text of a lesson tab, in WaniKani markup
+/// `components` holds one summary line per subject listed on the Composition tab
+pub fn tab_text(tab: LessonTab, data: &SubjectData, components: &[String]) -> String {
+    let mut lines = Vec::new();
+    match tab {
+        LessonTab::Composition => {
+            let label = match data {
+                SubjectData::Kanji(_) => "Radical Combination",
+                _ => "Kanji Composition",
+            };
+            lines.push(format!("{}:", label));
+            lines.extend(components.iter().map(|line| format!("  {}", line)));
+        }
+        LessonTab::Meaning => {
+            lines.push(format!(
+                "Primary: <meaning>{}</meaning>",
+                join_meanings(data.meanings().iter().filter(|m| m.primary == Some(true)))
+            ));
+            let alternatives = join_meanings(
+                data.meanings()
+                    .iter()
+                    .filter(|m| m.primary != Some(true))
+                    .chain(
+                        data.auxiliary_meanings()
+                            .iter()
+                            .filter(|m| m.type_.as_deref() == Some("whitelist")),
+                    ),
+            );
+            if !alternatives.is_empty() {
+                lines.push(format!("Alternatives: {}", alternatives));
+            }
+            if !data.parts_of_speech().is_empty() {
+                lines.push(format!(
+                    "Part of Speech: {}",
+                    data.parts_of_speech().join(", ")
+                ));
+            }
+            lines.push(format!("\nMnemonic:\n{}", data.meaning_mnemonic()));
+            if let SubjectData::Kanji(kanji) = data {
+                if let Some(hint) = &kanji.meaning_hint {
+                    lines.push(format!("\nHint:\n{}", hint));
+                }
+            }
+        }
+        LessonTab::Reading => {
+            if let SubjectData::Kanji(_) = data {
+                for (type_, label) in [
+                    ("onyomi", "On'yomi"),
+                    ("kunyomi", "Kun'yomi"),
+                    ("nanori", "Nanori"),
+                ] {
+                    lines.push(format!(
+                        "{}: {}",
+                        label,
+                        join_readings(data.readings_of_type(type_).into_iter())
+                    ));
+                }
+            } else {
+                lines.push(format!(
+                    "Readings: {}",
+                    join_readings(data.readings().iter())
+                ));
+            }
+            if let Some(mnemonic) = data.reading_mnemonic() {
+                lines.push(format!("\nMnemonic:\n{}", mnemonic));
+            }
+            if let SubjectData::Kanji(kanji) = data {
+                if let Some(hint) = &kanji.reading_hint {
+                    lines.push(format!("\nHint:\n{}", hint));
+                }
+            }
+        }
+        LessonTab::Context => {
+            if data.context_sentences().is_empty() {
+                lines.push("No context sentences.".to_string());
+            }
+            for sentence in data.context_sentences() {
+                lines.push(format!("<ja>{}</ja>\n{}\n", sentence.ja, sentence.en));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn join_meanings<'a>(meanings: impl Iterator<Item = &'a Meaning>) -> String {
+    meanings
+        .map(|m| m.meaning.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Comma separated readings with the primary ones highlighted, "None" if there are none
+fn join_readings<'a>(readings: impl Iterator<Item = &'a Reading>) -> String {
+    let readings: Vec<String> = readings
+        .map(|r| {
+            if r.primary {
+                format!("<reading>{}</reading>", r.reading)
+            } else {
+                r.reading.clone()
+            }
+        })
+        .collect();
+    if readings.is_empty() {
+        "None".to_string()
+    } else {
+        readings.join(", ")
+    }
+}
 
 pub struct LessonSession {
     client: WaniKaniClient,
     subject_ids: Vec<u64>,
     current_index: usize,
     display_method: String,
+    settings: Settings,
+    cache_dir: PathBuf,
 }
 
 impl LessonSession {
-    pub fn new(client: WaniKaniClient, subject_ids: Vec<u64>, display_method: String) -> Self {
+    pub fn new(
+        client: WaniKaniClient,
+        subject_ids: Vec<u64>,
+        display_method: String,
+        settings: Settings,
+        cache_dir: PathBuf,
+    ) -> Self {
         LessonSession {
             client,
             subject_ids,
             current_index: 0,
             display_method,
+            settings,
+            cache_dir,
         }
     }
 
-    pub fn start(&mut self) -> Result<()> {
+    /// Runs the session and returns what happened in it
+    pub fn start(&mut self) -> Result<SessionSummary> {
+        let mut summary = SessionSummary::new(SessionKind::Lessons);
         if self.subject_ids.is_empty() {
             display::display_text(&self.display_method, "No lessons available!");
-            return Ok(());
+            return Ok(summary);
         }
 
         display::display_text(
@@ -36,131 +202,219 @@ impl LessonSession {
             ),
         );
 
+        let mut cache = SubjectCache::load(&self.cache_dir)?;
+        cache.ensure(&self.client, &self.subject_ids)?;
+
         while self.current_index < self.subject_ids.len() {
-            self.show_current_lesson()?;
-            let command = display::text_input(
-                &self.display_method,
-                "Press Enter for next lesson, 'q' to quit",
-            );
-            if command == "q" {
+            let subject_id = self.subject_ids[self.current_index];
+            let subject = match cache.get(subject_id) {
+                Some(subject) => subject.clone(),
+                None => {
+                    display::display_text(
+                        &self.display_method,
+                        "Skipping a subject, your subscription does not include its level.",
+                    );
+                    self.current_index += 1;
+                    continue;
+                }
+            };
+
+            if !self.show_lesson(&mut cache, &subject)? {
                 break;
             }
+            summary.add(ItemResult::new(&subject));
             self.current_index += 1;
+            if !self.continue_after_batch() {
+                break;
+            }
         }
 
         display::display_text(&self.display_method, "Lesson session complete!");
-        Ok(())
+        summary.finish(0);
+        Ok(summary)
     }
 
-    fn show_current_lesson(&self) -> Result<()> {
-        let subject_id = self.subject_ids[self.current_index];
-        let subject = self.client.fetch_subject(subject_id)?;
+    /// Pauses between batches of lessons, returns false if the user wants to stop
+    fn continue_after_batch(&self) -> bool {
+        let batch_size = self.settings.lesson_batch_size.max(1) as usize;
+        if !self.current_index.is_multiple_of(batch_size)
+            || self.current_index >= self.subject_ids.len()
+        {
+            return true;
+        }
 
+        let command = display::text_input(
+            &self.display_method,
+            &format!(
+                "Batch complete ({}/{} lessons). Press Enter for the next batch, 'q' to quit",
+                self.current_index,
+                self.subject_ids.len()
+            ),
+        );
+        command != "q"
+    }
+
+    /// Shows the tabs of one lesson, returns false if the user wants to stop
+    fn show_lesson(&self, cache: &mut SubjectCache, subject: &Subject) -> Result<bool> {
         // Display the character
-        match &subject.data {
-            subject::SubjectData::KanaVocabulary(vocabulary) => {
-                let ascii_string =
-                    generate_ascii_text(&vocabulary.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
-            }
-            subject::SubjectData::Kanji(kanji) => {
-                let ascii_string = generate_ascii_text(&kanji.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
-            }
-            subject::SubjectData::Vocabulary(vocab) => {
-                let ascii_string = generate_ascii_text(&vocab.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
-            }
-            subject::SubjectData::Radical(radical) => {
-                if let Some(characters) = &radical.characters {
-                    let ascii_string = generate_ascii_text(characters, "togoshi-gothic", 90, 2);
-                    display::display_text(&self.display_method, ascii_string.as_str());
-                } else {
-                    let image_url = &radical.character_images[0].url;
-                    let response = reqwest::blocking::get(image_url)?;
-                    let svg_data = response.text()?;
-                    let image_ = utils::utils::svg_to_dynamic_image(&svg_data)?;
-                    let ascii_art = generate_ascii_image(&image_, 80, 24, 2);
-                    display::display_text(&self.display_method, &ascii_art);
+        let ascii_art = utils::utils::subject_ascii_art(
+            &subject.data,
+            &self.settings.font,
+            self.settings.ascii_art_width,
+        )?;
+        display::display_text(&self.display_method, &ascii_art);
+
+        cache.ensure(&self.client, subject.data.component_subject_ids())?;
+        let components: Vec<String> = subject
+            .data
+            .component_subject_ids()
+            .iter()
+            .filter_map(|id| cache.get(*id).map(summary_line))
+            .collect();
+
+        let tabs = lesson_tabs(&subject.data);
+        let audio_tab = tabs
+            .iter()
+            .position(|tab| *tab == LessonTab::Reading)
+            .unwrap_or(0);
+        let mut audio_played = false;
+        let mut current_tab = 0;
+
+        loop {
+            let tab = tabs[current_tab];
+            let tab_bar = tabs
+                .iter()
+                .enumerate()
+                .map(|(i, t)| {
+                    if i == current_tab {
+                        format!("[{}. {}]", i + 1, t.title())
+                    } else {
+                        format!(" {}. {} ", i + 1, t.title())
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            display::display_markup(
+                &self.display_method,
+                &format!(
+                    "\n{} - {} (level {}), lesson {}/{}\n{}\n\n{}\n",
+                    subject.data.characters().unwrap_or(subject.data.slug()),
+                    subject.data.type_name(),
+                    subject.data.level(),
+                    self.current_index + 1,
+                    self.subject_ids.len(),
+                    tab_bar,
+                    tab_text(tab, &subject.data, &components)
+                ),
+                self.settings.colors,
+            );
+
+            if self.settings.autoplay_audio && current_tab == audio_tab && !audio_played {
+                if let Some(audio) = subject.data.pronunciation_audios().first() {
+                    utils::utils::play_audio(&audio.url);
                 }
+                audio_played = true;
             }
-        }
 
-        // Display information
-        match &subject.data {
-            subject::SubjectData::Radical(radical) => {
-                display::display_text(
-                    &self.display_method,
-                    &format!(
-                        "\nMeaning: {}\nMnemonic: {}\n",
-                        radical
-                            .meanings
-                            .first()
-                            .map(|m| &m.meaning)
-                            .unwrap_or(&String::new()),
-                        radical.meaning_mnemonic
-                    ),
-                );
-            }
-            subject::SubjectData::Kanji(kanji) => {
-                display::display_text(
-                    &self.display_method,
-                    &format!(
-                        "\nMeaning: {}\nReading (hiragana or romaji): {}\nMeaning Mnemonic: {}\nReading Mnemonic: {}\n",
-                        kanji
-                            .meanings
-                            .first()
-                            .map(|m| &m.meaning)
-                            .unwrap_or(&String::new()),
-                        kanji
-                            .readings
-                            .first()
-                            .map(|r| &r.reading)
-                            .unwrap_or(&String::new()),
-                        kanji.meaning_mnemonic,
-                        kanji.reading_mnemonic
-                    ),
-                );
-            }
-            subject::SubjectData::Vocabulary(vocab) => {
-                display::display_text(
-                    &self.display_method,
-                    &format!(
-                        "\nMeaning: {}\nReading (hiragana or romaji): {}\nMeaning Mnemonic: {}\nReading Mnemonic: {}\n",
-                        vocab
-                            .meanings
-                            .first()
-                            .map(|m| &m.meaning)
-                            .unwrap_or(&String::new()),
-                        vocab
-                            .readings
-                            .first()
-                            .map(|r| &r.reading)
-                            .unwrap_or(&String::new()),
-                        vocab.meaning_mnemonic,
-                        vocab.reading_mnemonic
-                    ),
-                );
-            }
-            subject::SubjectData::KanaVocabulary(vocab) => {
-                display::display_text(
-                    &self.display_method,
-                    &format!(
-                        "\nMeaning: {}\nMeaning Mnemonic: {}\n",
-                        vocab
-                            .meanings
-                            .first()
-                            .map(|m| &m.meaning)
-                            .unwrap_or(&String::new()),
-                        vocab.meaning_mnemonic
+            let is_last_tab = current_tab + 1 == tabs.len();
+            let command = display::text_input(
+                &self.display_method,
+                &format!(
+                    "Press Enter for the {}, a number for a tab, 'p' for the previous tab, 'r' for related subjects, 'n' for your notes, 'q' to quit",
+                    if is_last_tab { "next lesson" } else { "next tab" }
+                ),
+            );
+            match command.as_str() {
+                "q" => return Ok(false),
+                "r" => SubjectBrowser::new(
+                    self.client.clone(),
+                    self.display_method.clone(),
+                    self.settings.colors,
+                )
+                .browse(cache, subject.id)?,
+                "n" => {
+                    notes::edit_notes(&self.display_method, &self.client, subject)?;
+                }
+                "p" => current_tab = current_tab.saturating_sub(1),
+                "" if is_last_tab => return Ok(true),
+                "" => current_tab += 1,
+                _ => match command.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= tabs.len() => current_tab = n - 1,
+                    _ => display::display_text(
+                        &self.display_method,
+                        "Invalid choice. Please try again.",
                     ),
-                );
+                },
             }
         }
-
-        Ok(())
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wanikani::subject::subject::KanjiData;
+    use serde_json::json;
 
+    fn kanji() -> SubjectData {
+        serde_json::from_value::<KanjiData>(json!({
+            "amalgamation_subject_ids": [],
+            "auxiliary_meanings": [{"meaning": "uno", "type": "whitelist"}],
+            "characters": "一",
+            "component_subject_ids": [1],
+            "created_at": "2024-01-01T00:00:00Z",
+            "document_url": "",
+            "hidden_at": null,
+            "lesson_position": 0,
+            "level": 1,
+            "meanings": [
+                {"meaning": "One", "primary": true, "accepted_answer": true},
+                {"meaning": "Single", "primary": false, "accepted_answer": true}
+            ],
+            "meaning_hint": "Count the lines.",
+            "meaning_mnemonic": "The <radical>ground</radical> is one.",
+            "readings": [
+                {"reading": "いち", "primary": true, "accepted_answer": true, "type": "onyomi"},
+                {"reading": "ひと", "primary": false, "accepted_answer": false, "type": "kunyomi"}
+            ],
+            "reading_mnemonic": "Itchy.",
+            "reading_hint": null,
+            "slug": "一",
+            "visually_similar_subject_ids": [],
+            "spaced_repetition_system_id": 1
+        }))
+        .map(SubjectData::Kanji)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_lesson_tabs() {
+        let data = kanji();
+        assert_eq!(
+            lesson_tabs(&data),
+            vec![
+                LessonTab::Composition,
+                LessonTab::Meaning,
+                LessonTab::Reading
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tab_text() {
+        let data = kanji();
+        assert_eq!(
+            tab_text(LessonTab::Composition, &data, &["ー - Ground".to_string()]),
+            "Radical Combination:\n  ー - Ground"
+        );
+
+        let meaning = tab_text(LessonTab::Meaning, &data, &[]);
+        assert!(meaning.starts_with("Primary: <meaning>One</meaning>\nAlternatives: Single, uno"));
+        assert!(meaning.ends_with("\nHint:\nCount the lines."));
 
+        let reading = tab_text(LessonTab::Reading, &data, &[]);
+        assert!(
+            reading.starts_with("On'yomi: <reading>いち</reading>\nKun'yomi: ひと\nNanori: None\n")
+        );
+    }
+}
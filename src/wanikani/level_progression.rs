@@ -0,0 +1,33 @@
+use crate::wanikani::decode::{from_rfc3339, from_rfc3339_option};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The user's progress through one level, there is one per level and reset
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelProgression {
+    pub id: u64,
+    pub object: String,
+    pub url: String,
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub data_updated_at: DateTime<Utc>,
+    pub data: LevelProgressionData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelProgressionData {
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub created_at: DateTime<Utc>,
+    pub level: u8,
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub unlocked_at: Option<DateTime<Utc>>,
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub passed_at: Option<DateTime<Utc>>,
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Set when a reset took the user back below this level
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub abandoned_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,357 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+/// Tags WaniKani uses in mnemonics and hints
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkupTag {
+    Radical,
+    Kanji,
+    Vocabulary,
+    Meaning,
+    Reading,
+    Japanese,
+}
+
+impl MarkupTag {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "radical" => Some(MarkupTag::Radical),
+            "kanji" => Some(MarkupTag::Kanji),
+            "vocabulary" => Some(MarkupTag::Vocabulary),
+            "meaning" => Some(MarkupTag::Meaning),
+            "reading" => Some(MarkupTag::Reading),
+            "ja" => Some(MarkupTag::Japanese),
+            _ => None,
+        }
+    }
+
+    /// ANSI escape sequence, using the 256 color palette closest to WaniKani's colors
+    fn ansi(&self) -> &'static str {
+        match self {
+            MarkupTag::Radical => "\x1B[1;38;5;39m",
+            MarkupTag::Kanji => "\x1B[1;38;5;199m",
+            MarkupTag::Vocabulary => "\x1B[1;38;5;129m",
+            MarkupTag::Meaning => "\x1B[1m",
+            MarkupTag::Reading => "\x1B[1;4m",
+            MarkupTag::Japanese => "",
+        }
+    }
+
+    fn style(&self) -> Style {
+        let style = Style::default().add_modifier(Modifier::BOLD);
+        match self {
+            MarkupTag::Radical => style.fg(Color::Indexed(39)),
+            MarkupTag::Kanji => style.fg(Color::Indexed(199)),
+            MarkupTag::Vocabulary => style.fg(Color::Indexed(129)),
+            MarkupTag::Meaning => style,
+            MarkupTag::Reading => style.add_modifier(Modifier::UNDERLINED),
+            MarkupTag::Japanese => Style::default(),
+        }
+    }
+}
+
+/// A run of text and the tags it is in, outermost first
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkupSpan {
+    pub text: String,
+    pub tags: Vec<MarkupTag>,
+}
+
+impl MarkupSpan {
+    /// Styles of all tags combined, inner tags override the color of outer ones
+    fn style(&self) -> Style {
+        self.tags
+            .iter()
+            .fold(Style::default(), |style, tag| style.patch(tag.style()))
+    }
+
+    fn ansi(&self) -> String {
+        self.tags.iter().map(|tag| tag.ansi()).collect()
+    }
+}
+
+/// Splits marked up text into spans
+/// Anything that is not one of WaniKani's tags, such as an unknown tag or a lone `<`, is kept as text
+pub fn parse(text: &str) -> Vec<MarkupSpan> {
+    let mut spans: Vec<MarkupSpan> = Vec::new();
+    let mut stack: Vec<MarkupTag> = Vec::new();
+    let mut text_start = 0;
+    let mut position = 0;
+
+    while let Some(offset) = text[position..].find('<') {
+        let start = position + offset;
+        let Some((closing, tag, length)) = parse_tag(&text[start..]) else {
+            position = start + 1;
+            continue;
+        };
+        push_text(&mut spans, &text[text_start..start], &stack);
+        if closing {
+            if let Some(open) = stack.iter().rposition(|open| *open == tag) {
+                stack.truncate(open);
+            }
+        } else {
+            stack.push(tag);
+        }
+        position = start + length;
+        text_start = position;
+    }
+
+    push_text(&mut spans, &text[text_start..], &stack);
+    spans
+}
+
+/// Reads a tag like `<kanji>` or `</kanji>` at the start of `text`
+/// Returns whether it closes, the tag and its length
+fn parse_tag(text: &str) -> Option<(bool, MarkupTag, usize)> {
+    let end = text.find('>')?;
+    let name = &text[1..end];
+    let (closing, name) = match name.strip_prefix('/') {
+        Some(name) => (true, name),
+        None => (false, name),
+    };
+    MarkupTag::from_name(name.trim()).map(|tag| (closing, tag, end + 1))
+}
+
+fn push_text(spans: &mut Vec<MarkupSpan>, text: &str, tags: &[MarkupTag]) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.tags == tags => last.text.push_str(text),
+        _ => spans.push(MarkupSpan {
+            text: text.to_string(),
+            tags: tags.to_vec(),
+        }),
+    }
+}
+
+/// Columns a character takes up in the terminal, CJK characters are double width
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6 => 2,
+        _ => 1,
+    }
+}
+
+/// Wraps spans into lines no wider than `width` columns, breaking between words where possible
+/// A word may span several tags, only words wider than a whole line are broken between characters
+pub fn wrap(spans: &[MarkupSpan], width: usize) -> Vec<Vec<MarkupSpan>> {
+    let mut wrapper = Wrapper {
+        width: width.max(1),
+        lines: vec![Vec::new()],
+        line_width: 0,
+        spaces: Vec::new(),
+        word: Vec::new(),
+        word_width: 0,
+    };
+
+    for span in spans {
+        for c in span.text.chars() {
+            match c {
+                '\n' => {
+                    wrapper.place_word();
+                    wrapper.spaces.clear();
+                    wrapper.break_line();
+                }
+                ' ' => {
+                    wrapper.place_word();
+                    wrapper.spaces.push(span.tags.clone());
+                }
+                _ => {
+                    push_text(&mut wrapper.word, &c.to_string(), &span.tags);
+                    wrapper.word_width += char_width(c);
+                }
+            }
+        }
+    }
+    wrapper.place_word();
+
+    wrapper.lines
+}
+
+struct Wrapper {
+    width: usize,
+    lines: Vec<Vec<MarkupSpan>>,
+    line_width: usize,
+    /// Tags of the spaces since the last word, written out before the next word on the same line
+    spaces: Vec<Vec<MarkupTag>>,
+    /// The word being read, which may span several tags
+    word: Vec<MarkupSpan>,
+    word_width: usize,
+}
+
+impl Wrapper {
+    fn break_line(&mut self) {
+        self.lines.push(Vec::new());
+        self.line_width = 0;
+    }
+
+    fn place_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let fits = self.line_width + self.spaces.len() + self.word_width <= self.width;
+        if self.line_width > 0 && !fits {
+            // Spaces at a wrap point are swallowed by the line break
+            self.spaces.clear();
+            self.break_line();
+        }
+        for tags in std::mem::take(&mut self.spaces) {
+            push_text(self.lines.last_mut().unwrap(), " ", &tags);
+            self.line_width += 1;
+        }
+
+        for span in std::mem::take(&mut self.word) {
+            for c in span.text.chars() {
+                let c_width = char_width(c);
+                if self.line_width > 0 && self.line_width + c_width > self.width {
+                    self.break_line();
+                }
+                push_text(self.lines.last_mut().unwrap(), &c.to_string(), &span.tags);
+                self.line_width += c_width;
+            }
+        }
+        self.word_width = 0;
+    }
+}
+
+pub fn to_plain(lines: &[Vec<MarkupSpan>]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|span| span.text.as_str())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn to_ansi(lines: &[Vec<MarkupSpan>]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|span| match span.ansi() {
+                    ansi if ansi.is_empty() => span.text.clone(),
+                    ansi => format!("{}{}\x1B[0m", ansi, span.text),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn to_lines(lines: &[Vec<MarkupSpan>], colors: bool) -> Vec<Line<'static>> {
+    lines
+        .iter()
+        .map(|line| {
+            Line::from(
+                line.iter()
+                    .map(|span| {
+                        if colors {
+                            Span::styled(span.text.clone(), span.style())
+                        } else {
+                            Span::raw(span.text.clone())
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, tags: &[MarkupTag]) -> MarkupSpan {
+        MarkupSpan {
+            text: text.to_string(),
+            tags: tags.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse("The <radical>ground</radical> is <reading>ち</reading>."),
+            vec![
+                span("The ", &[]),
+                span("ground", &[MarkupTag::Radical]),
+                span(" is ", &[]),
+                span("ち", &[MarkupTag::Reading]),
+                span(".", &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_and_unknown_tags() {
+        assert_eq!(
+            parse("<reading><ja>じ</ja> sound</reading> <b>bold</b>"),
+            vec![
+                span("じ", &[MarkupTag::Reading, MarkupTag::Japanese]),
+                span(" sound", &[MarkupTag::Reading]),
+                span(" <b>bold</b>", &[]),
+            ]
+        );
+        assert_eq!(
+            parse("a < b <kanji>c</kanji>"),
+            vec![span("a < b ", &[]), span("c", &[MarkupTag::Kanji])]
+        );
+        assert_eq!(
+            parse("<kanji>open"),
+            vec![span("open", &[MarkupTag::Kanji])]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let lines = wrap(&parse("one <kanji>two</kanji> three four"), 9);
+        assert_eq!(to_plain(&lines), "one two\nthree\nfour");
+        assert_eq!(lines[0][1], span("two", &[MarkupTag::Kanji]));
+
+        let lines = wrap(&parse("日本語の文です"), 6);
+        assert_eq!(to_plain(&lines), "日本語\nの文で\nす");
+
+        let lines = wrap(&parse("first\nsecond"), 20);
+        assert_eq!(to_plain(&lines), "first\nsecond");
+        // A word that changes tags in the middle still moves to the next line as a whole
+        let lines = wrap(&parse("ab <kanji>cd</kanji>ef gh"), 5);
+        assert_eq!(to_plain(&lines), "ab\ncdef\ngh");
+        assert_eq!(
+            lines[1],
+            vec![span("cd", &[MarkupTag::Kanji]), span("ef", &[])]
+        );
+        assert_eq!(lines[2], vec![span("gh", &[])]);
+    }
+
+    #[test]
+    fn test_ansi_output() {
+        let lines = wrap(&parse("<radical>ground</radical>!"), 80);
+        assert_eq!(to_ansi(&lines), "\x1B[1;38;5;39mground\x1B[0m!");
+
+        // Nested tags keep the style of the outer tag
+        let lines = wrap(&parse("<kanji><reading>じ</reading></kanji>"), 80);
+        assert_eq!(to_ansi(&lines), "\x1B[1;38;5;199m\x1B[1;4mじ\x1B[0m");
+        assert_eq!(
+            lines[0][0].style(),
+            Style::default()
+                .fg(Color::Indexed(199))
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::display;
+use crate::wanikani::study_material::{StudyMaterial, StudyMaterialUpdate};
+use crate::wanikani::subject::subject::Subject;
+use crate::WaniKaniClient;
+use anyhow::Result;
+
+#[derive(Clone, Copy)]
+enum NoteKind {
+    Meaning,
+    Reading,
+}
+
+impl NoteKind {
+    fn label(&self) -> &'static str {
+        match self {
+            NoteKind::Meaning => "Meaning Note",
+            NoteKind::Reading => "Reading Note",
+        }
+    }
+
+    fn get(&self, study_material: Option<&StudyMaterial>) -> String {
+        study_material
+            .and_then(|study_material| match self {
+                NoteKind::Meaning => study_material.data.meaning_note.clone(),
+                NoteKind::Reading => study_material.data.reading_note.clone(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// An empty note is sent as null, which removes it
+    fn update(&self, note: String) -> StudyMaterialUpdate {
+        let note = Some((!note.trim().is_empty()).then_some(note));
+        match self {
+            NoteKind::Meaning => StudyMaterialUpdate {
+                meaning_note: note,
+                ..Default::default()
+            },
+            NoteKind::Reading => StudyMaterialUpdate {
+                reading_note: note,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Whether the study materials changed on WaniKani since `base` was fetched
+fn has_conflict(base: Option<&StudyMaterial>, latest: Option<&StudyMaterial>) -> bool {
+    match (base, latest) {
+        (None, None) => false,
+        (Some(base), Some(latest)) => base.data_updated_at != latest.data_updated_at,
+        _ => true,
+    }
+}
+
+/// Shows the user's notes for a subject and lets them edit them
+/// Changes made elsewhere while editing are detected and the user decides which version to keep
+/// Returns the latest study materials of the subject
+pub fn edit_notes(
+    output_method: &str,
+    client: &WaniKaniClient,
+    subject: &Subject,
+) -> Result<Option<StudyMaterial>> {
+    let mut base = client.fetch_study_material(subject.id)?;
+    let has_reading = !subject.data.readings().is_empty();
+
+    loop {
+        let mut text = format!(
+            "Notes for {}\n\n{}:\n{}",
+            subject.data.characters().unwrap_or(subject.data.slug()),
+            NoteKind::Meaning.label(),
+            NoteKind::Meaning.get(base.as_ref())
+        );
+        if has_reading {
+            text.push_str(&format!(
+                "\n\n{}:\n{}",
+                NoteKind::Reading.label(),
+                NoteKind::Reading.get(base.as_ref())
+            ));
+        }
+        display::display_text(output_method, &text);
+
+        let command = display::text_input(
+            output_method,
+            if has_reading {
+                "Enter 'm' to edit the meaning note, 'r' to edit the reading note, or press Enter to go back:"
+            } else {
+                "Enter 'm' to edit the meaning note, or press Enter to go back:"
+            },
+        );
+        let kind = match command.as_str() {
+            "m" => NoteKind::Meaning,
+            "r" if has_reading => NoteKind::Reading,
+            _ => return Ok(base),
+        };
+
+        let current = kind.get(base.as_ref());
+        let note = match display::text_area(output_method, kind.label(), &current) {
+            Some(note) if note != current => note,
+            _ => continue,
+        };
+
+        let latest = client.fetch_study_material(subject.id)?;
+        if has_conflict(base.as_ref(), latest.as_ref()) {
+            display::display_text(
+                output_method,
+                &format!(
+                    "Your notes were changed elsewhere while you were editing. The {} on WaniKani is now:\n{}",
+                    kind.label().to_lowercase(),
+                    kind.get(latest.as_ref())
+                ),
+            );
+            let answer = display::text_input(
+                output_method,
+                "Enter 'o' to overwrite it with your version, or press Enter to keep the one on WaniKani:",
+            );
+            if answer != "o" {
+                base = latest;
+                continue;
+            }
+        }
+
+        let update = kind.update(note);
+        base = Some(client.save_study_material(subject.id, latest.as_ref(), &update)?);
+        display::display_text(
+            output_method,
+            &format!("Saved your {}.", kind.label().to_lowercase()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wanikani::study_material::StudyMaterialData;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn study_material(updated_day: u32) -> StudyMaterial {
+        let day = |day: u32| Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap();
+        StudyMaterial {
+            id: 1,
+            object: "study_material".to_string(),
+            url: String::new(),
+            data_updated_at: day(updated_day),
+            data: StudyMaterialData {
+                created_at: day(1),
+                hidden: false,
+                meaning_note: Some("note".to_string()),
+                meaning_synonyms: Vec::new(),
+                reading_note: None,
+                subject_id: 1,
+                subject_type: "kanji".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_empty_note_is_removed() {
+        let update = NoteKind::Reading.update(" ".to_string());
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            json!({ "reading_note": null })
+        );
+        let update = NoteKind::Meaning.update("note".to_string());
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            json!({ "meaning_note": "note" })
+        );
+    }
+
+    #[test]
+    fn test_has_conflict() {
+        let old = study_material(1);
+        let new = study_material(31);
+        assert!(!has_conflict(None, None));
+        assert!(!has_conflict(Some(&old), Some(&old)));
+        assert!(has_conflict(Some(&old), Some(&new)));
+        assert!(has_conflict(None, Some(&new)));
+    }
+}
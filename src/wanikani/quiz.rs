@@ -0,0 +1,70 @@
+use crate::wanikani::subject::subject::SubjectData;
+use crate::WaniKaniClient;
+use anyhow::Result;
+use rand::seq::SliceRandom;
+
+/// Started subjects of the given types in random order, at most `limit` of them unless it is 0
+pub fn learned_subject_ids(
+    client: &WaniKaniClient,
+    subject_types: &str,
+    limit: u16,
+) -> Result<Vec<u64>> {
+    let mut subject_ids: Vec<u64> = client
+        .fetch_assignments_matching(&[
+            "started=true".to_string(),
+            format!("subject_types={}", subject_types),
+        ])?
+        .iter()
+        .map(|assignment| assignment.data.subject_id)
+        .collect();
+    subject_ids.shuffle(&mut rand::thread_rng());
+    if limit > 0 {
+        subject_ids.truncate(limit as usize);
+    }
+    Ok(subject_ids)
+}
+
+pub fn accepted_readings(data: &SubjectData) -> Vec<String> {
+    match data {
+        SubjectData::KanaVocabulary(vocab) => vec![vocab.characters.clone()],
+        _ => data
+            .readings()
+            .iter()
+            .filter(|r| r.accepted_answer)
+            .map(|r| r.reading.clone())
+            .collect(),
+    }
+}
+
+pub fn is_kana(c: char) -> bool {
+    matches!(c, 'ぁ'..='ゖ' | 'ァ'..='ヺ' | 'ー')
+}
+
+/// Replaces the word in a context sentence with blanks
+/// Conjugated words are found by their stem, without the trailing okurigana
+pub fn blank_sentence(sentence: &str, characters: &str) -> Option<String> {
+    let stem = characters.trim_end_matches(is_kana);
+    [characters, stem]
+        .into_iter()
+        .filter(|word| !word.is_empty())
+        .find(|word| sentence.contains(word))
+        .map(|word| sentence.replacen(word, &"＿".repeat(word.chars().count()), 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blank_sentence() {
+        assert_eq!(
+            blank_sentence("私は学生です。", "学生"),
+            Some("私は＿＿です。".to_string())
+        );
+        assert_eq!(
+            blank_sentence("ご飯を食べた。", "食べる"),
+            Some("ご飯を＿べた。".to_string())
+        );
+        assert_eq!(blank_sentence("山に行く。", "川"), None);
+    }
+}
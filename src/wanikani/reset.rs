@@ -0,0 +1,26 @@
+use crate::wanikani::decode::{from_rfc3339, from_rfc3339_option};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A reset of the user's progress back to a lower level
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Reset {
+    pub id: u64,
+    pub object: String,
+    pub url: String,
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub data_updated_at: DateTime<Utc>,
+    pub data: ResetData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResetData {
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub created_at: DateTime<Utc>,
+    pub original_level: u8,
+    pub target_level: u8,
+    /// Not set until the user confirmed the reset
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub confirmed_at: Option<DateTime<Utc>>,
+}
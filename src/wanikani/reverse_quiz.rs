@@ -0,0 +1,287 @@
+use crate::config::Settings;
+use crate::display;
+use crate::wanikani::cache::SubjectCache;
+use crate::wanikani::quiz::{accepted_readings, blank_sentence, is_kana, learned_subject_ids};
+use crate::wanikani::subject::subject::{Subject, SubjectData};
+use crate::wanikani::utils::utils::{play_audio, validate_reading};
+use crate::WaniKaniClient;
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use std::path::PathBuf;
+
+/// Number of writings to choose from, the correct one included
+const CANDIDATES: usize = 4;
+
+/// Quiz going from English to Japanese: the meaning is shown and the reading and writing are asked for
+pub struct ReverseQuiz {
+    client: WaniKaniClient,
+    display_method: String,
+    cache_dir: PathBuf,
+    settings: Settings,
+    context_sentences: bool,
+    readings: Score,
+    writings: Score,
+}
+
+#[derive(Default)]
+struct Score {
+    correct: usize,
+    total: usize,
+}
+
+impl Score {
+    fn add(&mut self, correct: bool) {
+        self.total += 1;
+        if correct {
+            self.correct += 1;
+        }
+    }
+}
+
+impl ReverseQuiz {
+    pub fn new(
+        client: WaniKaniClient,
+        display_method: String,
+        cache_dir: PathBuf,
+        settings: Settings,
+    ) -> Self {
+        ReverseQuiz {
+            client,
+            display_method,
+            cache_dir,
+            settings,
+            context_sentences: false,
+            readings: Score::default(),
+            writings: Score::default(),
+        }
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        let mut cache = SubjectCache::load(&self.cache_dir)?;
+        if cache.is_empty() {
+            display::display_text(
+                &self.display_method,
+                "Downloading subjects, this may take a moment...",
+            );
+        }
+        if let Err(e) = cache.refresh(&self.client) {
+            // The saved subjects are enough for a quiz, only a first download has to succeed
+            if cache.is_empty() {
+                return Err(e);
+            }
+            display::display_text(
+                &self.display_method,
+                &format!(
+                    "Could not update the subjects, quizzing the saved ones: {}",
+                    e
+                ),
+            );
+        }
+
+        // Only quiz what was already learned, the rest of the cache just provides wrong choices
+        let subject_ids = learned_subject_ids(
+            &self.client,
+            "kanji,vocabulary,kana_vocabulary",
+            self.settings.review_session_size,
+        )?;
+        if subject_ids.is_empty() {
+            display::display_text(
+                &self.display_method,
+                "Finish some lessons first, the quiz only asks for items you have learned.",
+            );
+            return Ok(());
+        }
+        cache.ensure(&self.client, &subject_ids)?;
+
+        let answer = display::text_input(
+            &self.display_method,
+            "Show a context sentence with the word blanked out when there is one? (y/N)",
+        );
+        self.context_sentences = answer.eq_ignore_ascii_case("y");
+
+        for subject_id in subject_ids {
+            let Some(subject) = cache.get(subject_id) else {
+                continue;
+            };
+            if !self.quiz_subject(&cache, subject) {
+                break;
+            }
+        }
+
+        let mut result = format!(
+            "Quiz finished. Readings: {} of {} correct.",
+            self.readings.correct, self.readings.total
+        );
+        if self.writings.total > 0 {
+            result.push_str(&format!(
+                " Writings: {} of {} correct.",
+                self.writings.correct, self.writings.total
+            ));
+        }
+        display::display_text(&self.display_method, &result);
+        Ok(())
+    }
+
+    /// Asks for the reading and then the writing of a subject, returns false if the user quit
+    fn quiz_subject(&mut self, cache: &SubjectCache, subject: &Subject) -> bool {
+        let Some(characters) = subject.data.characters() else {
+            return true;
+        };
+        let readings = accepted_readings(&subject.data);
+        if readings.is_empty() {
+            return true;
+        }
+
+        let meanings = subject
+            .data
+            .meanings()
+            .iter()
+            .filter(|m| m.accepted_answer != Some(false))
+            .map(|m| m.meaning.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut prompt = format!("{} ({})", meanings, subject.data.type_name());
+        if self.context_sentences {
+            if let Some((sentence, en)) =
+                subject.data.context_sentences().iter().find_map(|s| {
+                    blank_sentence(&s.ja, characters).map(|sentence| (sentence, &s.en))
+                })
+            {
+                prompt.push_str(&format!("\n\n{}\n{}", sentence, en));
+            }
+        }
+        display::display_text(&self.display_method, &prompt);
+
+        let answer = display::text_input(
+            &self.display_method,
+            "Enter the reading (in hiragana or romaji), or 'q' to quit:",
+        );
+        if answer == "q" {
+            return false;
+        }
+        let correct = validate_reading(&answer, &readings);
+        self.readings.add(correct);
+        if correct && self.settings.autoplay_audio {
+            if let Some(audio) = subject.data.pronunciation_audios().first() {
+                play_audio(&audio.url);
+            }
+        }
+        display::display_text(
+            &self.display_method,
+            &format!(
+                "{} The reading is {}.",
+                if correct { "Correct!" } else { "Incorrect." },
+                readings.join(", ")
+            ),
+        );
+
+        // Kana vocabulary is written the way it is read, so there is nothing to choose
+        if matches!(subject.data, SubjectData::KanaVocabulary(_)) {
+            return true;
+        }
+
+        let mut pool: Vec<(String, Vec<String>)> = cache
+            .subjects()
+            .filter(|other| other.data.type_name() == subject.data.type_name())
+            .filter_map(|other| {
+                let characters = other.data.characters()?.to_string();
+                Some((characters, accepted_readings(&other.data)))
+            })
+            .collect();
+        pool.shuffle(&mut rand::thread_rng());
+        let mut candidates = rank_distractors(characters, &readings, &pool, CANDIDATES - 1);
+        candidates.push(characters.to_string());
+        candidates.shuffle(&mut rand::thread_rng());
+
+        let listing = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| format!("{}. {}", i + 1, candidate))
+            .collect::<Vec<_>>()
+            .join("\n");
+        display::display_text(
+            &self.display_method,
+            &format!("Which one is {}?\n{}", readings[0], listing),
+        );
+        let answer = display::text_input(
+            &self.display_method,
+            "Enter the number of the writing, or 'q' to quit:",
+        );
+        if answer == "q" {
+            return false;
+        }
+        let correct = answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| candidates.get(n.wrapping_sub(1)))
+            .is_some_and(|candidate| candidate == characters);
+        self.writings.add(correct);
+        display::display_text(
+            &self.display_method,
+            &format!(
+                "{} It is written {}.",
+                if correct { "Correct!" } else { "Incorrect." },
+                characters
+            ),
+        );
+        true
+    }
+}
+
+/// Picks wrong writings that are easy to confuse with `characters`
+/// Words with the same reading come first, then words sharing characters, then words of the same length
+pub fn rank_distractors(
+    characters: &str,
+    readings: &[String],
+    pool: &[(String, Vec<String>)],
+    count: usize,
+) -> Vec<String> {
+    let length = characters.chars().count();
+    let mut ranked: Vec<(u8, &str)> = pool
+        .iter()
+        .filter(|(other, _)| other != characters)
+        .map(|(other, other_readings)| {
+            let homophone = other_readings.iter().any(|r| readings.contains(r));
+            let shares_character = other.chars().any(|c| !is_kana(c) && characters.contains(c));
+            let same_length = other.chars().count() == length;
+            let score = 4 * homophone as u8 + 2 * shares_character as u8 + same_length as u8;
+            (score, other.as_str())
+        })
+        .collect();
+    // Stable, so equally good choices keep the order of the pool
+    ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    let mut distractors: Vec<String> = Vec::new();
+    for (_, other) in ranked {
+        if distractors.len() == count {
+            break;
+        }
+        if !distractors.iter().any(|d| d == other) {
+            distractors.push(other.to_string());
+        }
+    }
+    distractors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_distractors() {
+        let entry =
+            |characters: &str, reading: &str| (characters.to_string(), vec![reading.to_string()]);
+        let pool = vec![
+            entry("大人", "おとな"),
+            entry("山", "やま"),
+            entry("小人", "こびと"),
+            entry("機械", "きかい"),
+            entry("機会", "きかい"),
+            entry("機会", "きかい"),
+        ];
+        assert_eq!(
+            rank_distractors("機会", &["きかい".to_string()], &pool, 2),
+            vec!["機械".to_string(), "大人".to_string()]
+        );
+    }
+}
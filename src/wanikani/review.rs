@@ -0,0 +1,28 @@
+use crate::wanikani::decode::from_rfc3339;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A review as returned by WaniKani after it was submitted
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Review {
+    pub id: u64,
+    pub object: String,
+    pub url: String,
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub data_updated_at: DateTime<Utc>,
+    pub data: ReviewData,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewData {
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub created_at: DateTime<Utc>,
+    pub assignment_id: u64,
+    pub subject_id: u64,
+    pub spaced_repetition_system_id: u64,
+    pub starting_srs_stage: u8,
+    pub ending_srs_stage: u8,
+    pub incorrect_meaning_answers: u32,
+    pub incorrect_reading_answers: u32,
+}
@@ -0,0 +1,166 @@
+use crate::wanikani::api::join_ids;
+use crate::wanikani::decode::from_rfc3339;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How well the user has answered a subject over all of its reviews
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewStatistic {
+    pub id: u64,
+    pub object: String,
+    pub url: String,
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub data_updated_at: DateTime<Utc>,
+    pub data: ReviewStatisticData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewStatisticData {
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub created_at: DateTime<Utc>,
+    pub hidden: bool,
+    pub meaning_correct: u32,
+    pub meaning_incorrect: u32,
+    pub meaning_max_streak: u32,
+    pub meaning_current_streak: u32,
+    pub percentage_correct: u8,
+    pub reading_correct: u32,
+    pub reading_incorrect: u32,
+    pub reading_max_streak: u32,
+    pub reading_current_streak: u32,
+    pub subject_id: u64,
+    pub subject_type: String,
+}
+
+#[cfg(test)]
+impl ReviewStatisticData {
+    /// A kanji answered correctly every time, for tests to adjust with struct update syntax
+    pub fn sample(subject_id: u64) -> Self {
+        use chrono::TimeZone;
+        ReviewStatisticData {
+            created_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            hidden: false,
+            meaning_correct: 1,
+            meaning_incorrect: 0,
+            meaning_max_streak: 1,
+            meaning_current_streak: 1,
+            percentage_correct: 100,
+            reading_correct: 1,
+            reading_incorrect: 0,
+            reading_max_streak: 1,
+            reading_current_streak: 1,
+            subject_id,
+            subject_type: "kanji".to_string(),
+        }
+    }
+}
+
+/// Filters supported by the review statistics endpoint, all of them optional
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReviewStatisticFilters {
+    pub subject_ids: Vec<u64>,
+    /// Only statistics with a higher percentage of correct answers
+    pub percentages_greater_than: Option<u8>,
+    /// Only statistics with a lower percentage of correct answers
+    pub percentages_less_than: Option<u8>,
+    pub hidden: Option<bool>,
+    pub updated_after: Option<DateTime<Utc>>,
+}
+
+impl ReviewStatisticFilters {
+    /// The filters as query parameters
+    pub fn params(&self) -> Vec<String> {
+        let mut params = Vec::new();
+        if !self.subject_ids.is_empty() {
+            params.push(format!("subject_ids={}", join_ids(&self.subject_ids)));
+        }
+        if let Some(percentage) = self.percentages_greater_than {
+            params.push(format!("percentages_greater_than={}", percentage));
+        }
+        if let Some(percentage) = self.percentages_less_than {
+            params.push(format!("percentages_less_than={}", percentage));
+        }
+        if let Some(hidden) = self.hidden {
+            params.push(format!("hidden={}", hidden));
+        }
+        if let Some(updated_after) = self.updated_after {
+            params.push(format!(
+                "updated_after={}",
+                updated_after.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ));
+        }
+        params
+    }
+
+    /// Whether a statistic passes the filters, the same way WaniKani applies them
+    pub fn matches(&self, statistic: &ReviewStatistic) -> bool {
+        let data = &statistic.data;
+        (self.subject_ids.is_empty() || self.subject_ids.contains(&data.subject_id))
+            && self
+                .percentages_greater_than
+                .is_none_or(|percentage| data.percentage_correct > percentage)
+            && self
+                .percentages_less_than
+                .is_none_or(|percentage| data.percentage_correct < percentage)
+            && self.hidden.is_none_or(|hidden| data.hidden == hidden)
+            && self
+                .updated_after
+                .is_none_or(|updated_after| statistic.data_updated_at > updated_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn statistic(subject_id: u64, percentage_correct: u8) -> ReviewStatistic {
+        ReviewStatistic {
+            id: subject_id,
+            object: "review_statistic".to_string(),
+            url: String::new(),
+            data_updated_at: Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap(),
+            data: ReviewStatisticData {
+                percentage_correct,
+                ..ReviewStatisticData::sample(subject_id)
+            },
+        }
+    }
+
+    #[test]
+    fn test_filter_params() {
+        let filters = ReviewStatisticFilters {
+            subject_ids: vec![1, 2],
+            percentages_less_than: Some(80),
+            hidden: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            filters.params(),
+            vec![
+                "subject_ids=1,2",
+                "percentages_less_than=80",
+                "hidden=false"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filters = ReviewStatisticFilters {
+            percentages_greater_than: Some(50),
+            percentages_less_than: Some(90),
+            ..Default::default()
+        };
+        assert!(filters.matches(&statistic(1, 75)));
+        assert!(!filters.matches(&statistic(1, 90)));
+        assert!(!filters.matches(&statistic(1, 50)));
+
+        let filters = ReviewStatisticFilters {
+            subject_ids: vec![2],
+            ..Default::default()
+        };
+        assert!(!filters.matches(&statistic(1, 75)));
+    }
+}
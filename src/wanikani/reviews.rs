@@ -1,46 +1,129 @@
+use crate::config::{ReviewOrder, Settings};
 use crate::display;
+use crate::wanikani::assignment::Assignment;
+use crate::wanikani::cache::SrsSystemCache;
+use crate::wanikani::history::History;
+use crate::wanikani::lessons::{tab_text, LessonTab};
+use crate::wanikani::notes;
+use crate::wanikani::review::Review;
+use crate::wanikani::session_summary::{ItemResult, SessionKind, SessionSummary};
+use crate::wanikani::srs::{self, SrsSystem};
+use crate::wanikani::study_material::{add_synonym, StudyMaterial, StudyMaterialUpdate};
 use crate::wanikani::subject::subject;
 use crate::wanikani::utils;
 use crate::WaniKaniClient;
 use anyhow::Result;
-use okanimoji::{generate_ascii_image, generate_ascii_text};
+use chrono::{Local, Utc};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Clone)]
 struct ReviewItem {
     subject_id: u64,
     assignment_id: u64,
+    srs_stage: u8,
     incorrect_meaning_answers: u32,
     incorrect_reading_answers: u32,
     needs_meaning: bool,
     needs_reading: bool,
+    /// Whether the item was shown at least once
+    started: bool,
 }
 
 pub struct ReviewSession {
     client: WaniKaniClient,
     assignments: HashMap<u64, ReviewItem>,
+    /// Subject ids in presentation order, the first `review_batch_size` are in rotation
+    queue: Vec<u64>,
     current_item: Option<u64>,
     display_method: String,
+    cache_dir: PathBuf,
+    settings: Settings,
+    /// The user's notes and synonyms, by subject id
+    study_materials: HashMap<u64, StudyMaterial>,
+    /// Whether the info panel is shown after every answer
+    show_info: bool,
+    /// Number of answers undone during the session
+    undo_count: u32,
+    /// Set when the user quits, unfinished items are left unsubmitted
+    quit: bool,
+    /// Extra study sessions quiz without submitting answers to WaniKani
+    extra_study: bool,
+    /// Local history finished items are recorded in as soon as they are answered
+    history: Option<History>,
+    summary: SessionSummary,
+}
+
+/// Sorts assignments in the order they should be presented in
+pub fn order_assignments(assignments: &mut [Assignment], order: ReviewOrder) {
+    match order {
+        ReviewOrder::Random => assignments.shuffle(&mut rand::thread_rng()),
+        ReviewOrder::OldestFirst => assignments.sort_by_key(|a| a.data.available_at),
+        ReviewOrder::LowestStageFirst => assignments.sort_by_key(|a| a.data.srs_stage),
+    }
+}
+
+/// Everything worth knowing about a subject after answering it, in WaniKani markup
+pub fn info_panel(
+    data: &subject::SubjectData,
+    study_material: Option<&StudyMaterial>,
+    srs: &str,
+) -> String {
+    let mut sections = vec![tab_text(LessonTab::Meaning, data, &[])];
+    if !data.readings().is_empty() {
+        sections.push(tab_text(LessonTab::Reading, data, &[]));
+    }
+
+    if let Some(study_material) = study_material {
+        let study_material = &study_material.data;
+        if !study_material.meaning_synonyms.is_empty() {
+            sections.push(format!(
+                "Your Synonyms: {}",
+                study_material.meaning_synonyms.join(", ")
+            ));
+        }
+        if let Some(note) = &study_material.meaning_note {
+            sections.push(format!("Meaning Note:\n{}", note));
+        }
+        if let Some(note) = &study_material.reading_note {
+            sections.push(format!("Reading Note:\n{}", note));
+        }
+    }
+
+    sections.push(format!("SRS: {}", srs));
+    sections.join("\n\n")
 }
 
 impl ReviewSession {
     pub fn new(
         client: WaniKaniClient,
-        assignment_ids: Vec<(u64, u64)>,
+        assignments: &[Assignment],
         display_method: String,
+        cache_dir: PathBuf,
+        settings: Settings,
     ) -> Self {
-        let assignments = assignment_ids
-            .into_iter()
-            .map(|(assignment_id, subject_id)| {
+        let session_size = match settings.review_session_size {
+            0 => assignments.len(),
+            size => size as usize,
+        };
+        let assignments = &assignments[..session_size.min(assignments.len())];
+        let queue = assignments.iter().map(|a| a.data.subject_id).collect();
+        let assignments = assignments
+            .iter()
+            .map(|assignment| {
                 (
-                    subject_id,
+                    assignment.data.subject_id,
                     ReviewItem {
-                        subject_id,
-                        assignment_id,
+                        subject_id: assignment.data.subject_id,
+                        assignment_id: assignment.id,
+                        srs_stage: assignment.data.srs_stage,
                         incorrect_meaning_answers: 0,
                         incorrect_reading_answers: 0,
                         needs_meaning: true,
                         needs_reading: true,
+                        started: false,
                     },
                 )
             })
@@ -49,36 +132,168 @@ impl ReviewSession {
         ReviewSession {
             client,
             assignments,
+            queue,
             current_item: None,
             display_method,
+            cache_dir,
+            settings,
+            study_materials: HashMap::new(),
+            show_info: false,
+            undo_count: 0,
+            quit: false,
+            extra_study: false,
+            history: None,
+            summary: SessionSummary::new(SessionKind::Reviews),
         }
     }
 
-    pub fn start(&mut self) -> Result<()> {
+    /// Records every finished item in `history` straight away,
+    /// so the answers are kept even if the session fails later on
+    pub fn record_history(mut self, history: History) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Turns the session into extra study: the same questions, but nothing is submitted
+    pub fn extra_study(mut self) -> Self {
+        self.extra_study = true;
+        self.summary = SessionSummary::new(SessionKind::ExtraStudy);
+        self
+    }
+
+    /// Runs the session and returns what happened in it
+    pub fn start(&mut self) -> Result<SessionSummary> {
         if self.assignments.is_empty() {
             display::display_text(&self.display_method, "No reviews available!");
-            return Ok(());
+            return Ok(self.summary.clone());
         }
 
         display::display_text(
             &self.display_method,
-            &format!(
-                "Starting review session with {} items",
-                self.assignments.len()
-            ),
+            &if self.extra_study {
+                format!(
+                    "Starting extra study with {} items, answers are not submitted to WaniKani",
+                    self.assignments.len()
+                )
+            } else {
+                format!(
+                    "Starting review session with {} items",
+                    self.assignments.len()
+                )
+            },
         );
 
-        while !self.assignments.is_empty() {
+        // Without the study materials only the user's synonyms and notes are missing
+        match self.client.fetch_study_materials(&self.queue) {
+            Ok(study_materials) => {
+                self.study_materials = study_materials
+                    .into_iter()
+                    .map(|study_material| (study_material.data.subject_id, study_material))
+                    .collect();
+            }
+            Err(e) => display::display_text(
+                &self.display_method,
+                &format!(
+                    "Could not load your synonyms and notes, reviewing without them: {}",
+                    e
+                ),
+            ),
+        }
+        // Without the SRS systems the intervals fall back to WaniKani's default system
+        match SrsSystemCache::load(&self.cache_dir) {
+            Ok(mut cache) => {
+                if let Err(e) = cache.refresh_if_stale(&self.client) {
+                    display::display_text(
+                        &self.display_method,
+                        &format!(
+                            "Could not update the SRS systems, using the saved ones: {}",
+                            e
+                        ),
+                    );
+                }
+                self.summary.srs_systems = cache.systems();
+            }
+            Err(e) => display::display_text(
+                &self.display_method,
+                &format!(
+                    "Could not load the SRS systems, using the default intervals: {}",
+                    e
+                ),
+            ),
+        }
+
+        while !self.assignments.is_empty() && !self.quit {
             self.select_next_item();
             self.process_current_item()?;
         }
 
-        display::display_text(&self.display_method, "Review session complete!");
-        Ok(())
+        let mut message = if self.quit && !self.assignments.is_empty() {
+            format!(
+                "Review session ended, {} unfinished items were not submitted.",
+                self.assignments.len()
+            )
+        } else {
+            "Review session complete!".to_string()
+        };
+        if self.undo_count > 0 {
+            message.push_str(&format!(" Answers undone: {}", self.undo_count));
+        }
+        display::display_text(&self.display_method, &message);
+
+        let unfinished = self
+            .assignments
+            .values()
+            .filter(|item| item.started)
+            .count();
+        self.summary.finish(unfinished);
+        Ok(self.summary.clone())
+    }
+
+    fn batch_len(&self) -> usize {
+        (self.settings.review_batch_size.max(1) as usize).min(self.queue.len())
     }
 
     fn select_next_item(&mut self) {
-        self.current_item = self.assignments.keys().next().copied();
+        let index = match self.settings.review_order {
+            ReviewOrder::Random if !self.queue.is_empty() => {
+                rand::thread_rng().gen_range(0..self.batch_len())
+            }
+            _ => 0,
+        };
+        self.current_item = self.queue.get(index).copied();
+    }
+
+    /// Stops introducing new items, only the ones already shown are left to finish
+    fn wrap_up(&mut self) {
+        self.assignments.retain(|_, item| item.started);
+        let assignments = &self.assignments;
+        self.queue.retain(|id| assignments.contains_key(id));
+        display::display_text(
+            &self.display_method,
+            &format!(
+                "Wrapping up, {} items left to finish.",
+                self.assignments.len()
+            ),
+        );
+    }
+
+    /// Handles 'w' and 'q' entered instead of an answer, returns whether it was one of them
+    fn session_command(&mut self, answer: &str) -> bool {
+        match answer {
+            "w" => self.wrap_up(),
+            "q" => self.quit = true,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Moves an unfinished item to the back of the items in rotation
+    fn requeue_item(&mut self, subject_id: u64) {
+        if let Some(position) = self.queue.iter().position(|id| *id == subject_id) {
+            self.queue.remove(position);
+            let batch_end = self.batch_len();
+            self.queue.insert(batch_end, subject_id);
+        }
     }
 
     fn process_current_item(&mut self) -> Result<()> {
@@ -87,34 +302,34 @@ impl ReviewSession {
             None => return Ok(()),
         };
 
-        let item = self.assignments.get(&subject_id).cloned().unwrap();
+        let mut item = self.assignments.get(&subject_id).cloned().unwrap();
         let subject = self.client.fetch_subject(item.subject_id)?;
-        let mut item_updated = false;
+        if !self.client.is_level_granted(subject.data.level()) {
+            // Locked by the subscription, it cannot be reviewed so leave it unanswered
+            self.assignments.remove(&subject_id);
+            self.queue.retain(|id| *id != subject_id);
+            return Ok(());
+        }
+        if let subject::SubjectData::Radical(_) = subject.data {
+            item.needs_reading = false;
+        }
+        item.started = true;
+        self.assignments.insert(subject_id, item.clone());
 
         if item.needs_meaning {
-            let processed_item = self.process_meaning(&subject, item.clone())?;
-            if let Some(updated_item) = processed_item {
-                self.assignments.insert(subject_id, updated_item);
-                item_updated = true;
-            }
+            item = self.process_meaning(&subject, item)?;
         }
-
-        let item = self.assignments.get(&subject_id).cloned().unwrap();
-        if item.needs_reading {
-            let processed_item = self.process_reading(&subject, item)?;
-            if let Some(updated_item) = processed_item {
-                self.assignments.insert(subject_id, updated_item);
-                item_updated = true;
-            }
+        if item.needs_reading && !self.quit {
+            item = self.process_reading(&subject, item)?;
         }
 
-        if !item_updated
-            || (!self.assignments.get(&subject_id).unwrap().needs_meaning
-                && !self.assignments.get(&subject_id).unwrap().needs_reading)
-        {
-            let final_item = self.assignments.get(&subject_id).unwrap();
-            self.submit_review(final_item)?;
+        if item.needs_meaning || item.needs_reading {
+            self.assignments.insert(subject_id, item);
+            self.requeue_item(subject_id);
+        } else {
+            // Submitted once the last answer was given
             self.assignments.remove(&subject_id);
+            self.queue.retain(|id| *id != subject_id);
         }
 
         Ok(())
@@ -123,119 +338,300 @@ impl ReviewSession {
     fn process_meaning(
         &mut self,
         subject: &subject::Subject,
-        mut item: ReviewItem,
-    ) -> Result<Option<ReviewItem>> {
-        match &subject.data {
-            subject::SubjectData::KanaVocabulary(vocabulary) => {
-                let ascii_string =
-                    generate_ascii_text(&vocabulary.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
-            }
-            subject::SubjectData::Kanji(kanji) => {
-                let ascii_string = generate_ascii_text(&kanji.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
+        item: ReviewItem,
+    ) -> Result<ReviewItem> {
+        loop {
+            self.display_subject(subject)?;
+
+            display::display_text(
+                &self.display_method,
+                "\nEnter the meaning ('w' to wrap up, 'q' to quit):",
+            );
+            let answer = display::text_input(&self.display_method, "");
+            if self.session_command(&answer) {
+                if self.quit {
+                    return Ok(item);
+                }
+                continue;
             }
-            subject::SubjectData::Vocabulary(vocab) => {
-                let ascii_string = generate_ascii_text(&vocab.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
+            let correct = self.check_meaning(subject, &answer);
+
+            let mut answered = item.clone();
+            if correct {
+                answered.needs_meaning = false;
+            } else {
+                answered.incorrect_meaning_answers += 1;
             }
-            subject::SubjectData::Radical(radical) => {
-                if let Some(characters) = &radical.characters {
-                    let ascii_string = generate_ascii_text(characters, "togoshi-gothic", 90, 2);
-                    display::display_text(&self.display_method, ascii_string.as_str());
-                } else {
-                    let image_url = &radical.character_images[0].url;
-                    let response = reqwest::blocking::get(image_url)?;
-                    let svg_data = response.text()?;
-                    let image_ = utils::utils::svg_to_dynamic_image(&svg_data)?;
-                    let ascii_art = generate_ascii_image(&image_, 80, 24, 2);
-                    display::display_text(&self.display_method, &ascii_art);
-                }
+            if self.show_answer_result(subject, &answered, correct, Some(&answer))? {
+                return Ok(answered);
             }
         }
+    }
 
-        display::display_text(&self.display_method, "\nEnter the meaning:");
-        let answer = display::text_input(&self.display_method, "");
-        let correct = self.check_meaning(subject, &answer);
+    fn process_reading(
+        &mut self,
+        subject: &subject::Subject,
+        item: ReviewItem,
+    ) -> Result<ReviewItem> {
+        loop {
+            self.display_subject(subject)?;
 
-        if correct {
-            display::display_text(&self.display_method, "Correct!");
-            item.needs_meaning = false;
-            Ok(Some(item))
-        } else {
-            display::display_text(&self.display_method, "Incorrect. Try again.");
-            item.incorrect_meaning_answers += 1;
-            Ok(Some(item))
+            display::display_text(
+                &self.display_method,
+                "\nEnter the reading in hiragana or romaji ('w' to wrap up, 'q' to quit):",
+            );
+            let answer = display::text_input(&self.display_method, "");
+            if self.session_command(&answer) {
+                if self.quit {
+                    return Ok(item);
+                }
+                continue;
+            }
+            let correct = self.check_reading(subject, &answer);
+
+            let mut answered = item.clone();
+            if correct {
+                if self.settings.autoplay_audio {
+                    if let Some(audio) = subject.data.pronunciation_audios().first() {
+                        utils::utils::play_audio(&audio.url);
+                    }
+                }
+                answered.needs_reading = false;
+            } else {
+                answered.incorrect_reading_answers += 1;
+            }
+            if self.show_answer_result(subject, &answered, correct, None)? {
+                return Ok(answered);
+            }
         }
     }
 
-    fn process_reading(
+    /// Tells the user how they did and shows the info panel when it is toggled on,
+    /// then submits the review if this was the last answer for the item, except in extra study
+    /// `meaning_answer` is set after meaning questions, offering to add a synonym
+    /// Returns false if the user undid the answer, so the question is asked again
+    fn show_answer_result(
         &mut self,
         subject: &subject::Subject,
-        mut item: ReviewItem,
-    ) -> Result<Option<ReviewItem>> {
-        if let subject::SubjectData::Radical(_) = subject.data {
-            item.needs_reading = false;
-            return Ok(Some(item));
-        }
+        item: &ReviewItem,
+        correct: bool,
+        meaning_answer: Option<&str>,
+    ) -> Result<bool> {
+        let finished = !item.needs_meaning && !item.needs_reading;
+        let system = self.srs_system(subject);
+        let srs = if finished && !self.extra_study {
+            let incorrect_answers = item.incorrect_meaning_answers + item.incorrect_reading_answers;
+            // WaniKani only reports the new stage once the review is submitted after this screen
+            let mut srs = format!(
+                "{} -> {} (expected)",
+                system.stage(item.srs_stage),
+                system.next_stage(item.srs_stage, incorrect_answers)
+            );
+            if let Some(next_review_at) =
+                system.next_review_at(item.srs_stage, incorrect_answers, Utc::now())
+            {
+                srs.push_str(&format!(
+                    "\nNext review: {}",
+                    next_review_at
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M")
+                ));
+            }
+            srs
+        } else {
+            system.stage(item.srs_stage).to_string()
+        };
+
+        let result = if correct {
+            "Correct!"
+        } else {
+            "Incorrect. Try again."
+        };
+        display::display_text(&self.display_method, result);
 
-        match &subject.data {
-            subject::SubjectData::KanaVocabulary(vocabulary) => {
-                let ascii_string =
-                    generate_ascii_text(&vocabulary.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
+        loop {
+            if self.show_info {
+                display::display_markup(
+                    &self.display_method,
+                    &info_panel(&subject.data, self.study_materials.get(&subject.id), &srs),
+                    self.settings.colors,
+                );
             }
-            subject::SubjectData::Kanji(kanji) => {
-                let ascii_string = generate_ascii_text(&kanji.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
+            let mut commands = vec![if self.show_info {
+                "'i' to hide item info"
+            } else {
+                "'i' to show item info"
+            }];
+            commands.push("'n' to edit your notes");
+            if meaning_answer.is_some() {
+                commands.push("'s' to add a synonym");
             }
-            subject::SubjectData::Vocabulary(vocab) => {
-                let ascii_string = generate_ascii_text(&vocab.characters, "togoshi-gothic", 90, 2);
-                display::display_text(&self.display_method, ascii_string.as_str());
+            if self.settings.allow_undo {
+                commands.push("'u' to undo this answer");
+            }
+            commands.push("'w' to wrap up");
+            commands.push("'q' to quit");
+            let command = display::text_input(
+                &self.display_method,
+                &format!("Press Enter to continue, {}", commands.join(", ")),
+            );
+            match (command.as_str(), meaning_answer) {
+                ("i", _) => self.show_info = !self.show_info,
+                ("n", _) => {
+                    if let Some(study_material) =
+                        notes::edit_notes(&self.display_method, &self.client, subject)?
+                    {
+                        self.study_materials.insert(subject.id, study_material);
+                    }
+                }
+                ("s", Some(answer)) => self.add_synonym(subject.id, answer)?,
+                ("u", _) if self.settings.allow_undo => {
+                    self.undo_count += 1;
+                    display::display_text(
+                        &self.display_method,
+                        "Answer undone, please answer again.",
+                    );
+                    return Ok(false);
+                }
+                ("w", _) => {
+                    self.wrap_up();
+                    break;
+                }
+                ("q", _) => {
+                    self.quit = true;
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        if finished {
+            let mut result = ItemResult::new(subject);
+            result.incorrect_meaning_answers = item.incorrect_meaning_answers;
+            result.incorrect_reading_answers = item.incorrect_reading_answers;
+            if !self.extra_study {
+                let review = self.submit_review(item)?;
+                result.starting_srs_stage = Some(review.data.starting_srs_stage);
+                result.ending_srs_stage = Some(review.data.ending_srs_stage);
+                if self.show_info {
+                    display::display_text(
+                        &self.display_method,
+                        &format!(
+                            "Submitted, SRS: {} -> {}",
+                            system.stage(review.data.starting_srs_stage),
+                            system.stage(review.data.ending_srs_stage)
+                        ),
+                    );
+                }
             }
-            subject::SubjectData::Radical(_) => {}
+            if let Some(history) = &mut self.history {
+                history.record(self.summary.kind, &result);
+                if let Err(e) = history.save() {
+                    display::display_text(
+                        &self.display_method,
+                        &format!("Failed to record your answer: {}", e),
+                    );
+                }
+            }
+            self.summary.add(result);
         }
+        Ok(true)
+    }
+
+    /// The SRS system the subject follows, WaniKani's default if it is not known
+    fn srs_system(&self, subject: &subject::Subject) -> SrsSystem {
+        srs::find_system(
+            &self.summary.srs_systems,
+            subject.data.spaced_repetition_system_id(),
+        )
+    }
+
+    /// Asks for a synonym, suggesting the given answer, and saves it to WaniKani
+    fn add_synonym(&mut self, subject_id: u64, answer: &str) -> Result<()> {
+        let input = display::text_input(
+            &self.display_method,
+            &format!(
+                "Synonym to add (press Enter to add \"{}\", 'q' to cancel):",
+                answer.trim()
+            ),
+        );
+        let synonym = match input.as_str() {
+            "q" => return Ok(()),
+            "" => answer,
+            synonym => synonym,
+        };
+
+        // Synonyms added elsewhere since the session started would be lost by writing the old list
+        let latest = self.client.fetch_study_material(subject_id)?;
+        let synonyms = latest
+            .as_ref()
+            .map(|study_material| study_material.data.meaning_synonyms.as_slice())
+            .unwrap_or_default();
+        let synonyms = match add_synonym(synonyms, synonym) {
+            Ok(synonyms) => synonyms,
+            Err(e) => {
+                display::display_text(&self.display_method, &e);
+                return Ok(());
+            }
+        };
 
+        let update = StudyMaterialUpdate {
+            meaning_synonyms: Some(synonyms),
+            ..Default::default()
+        };
+        let study_material =
+            self.client
+                .save_study_material(subject_id, latest.as_ref(), &update)?;
         display::display_text(
             &self.display_method,
-            "\nEnter the reading (in hiragana or romaji):",
+            &format!("Added \"{}\" to your synonyms.", synonym.trim()),
         );
-        let answer = display::text_input(&self.display_method, "");
-        let correct = self.check_reading(subject, &answer);
+        self.study_materials.insert(subject_id, study_material);
+        Ok(())
+    }
 
-        if correct {
-            display::display_text(&self.display_method, "Correct!");
-            item.needs_reading = false;
-            Ok(Some(item))
-        } else {
-            display::display_text(&self.display_method, "Incorrect. Try again.");
-            item.incorrect_reading_answers += 1;
-            Ok(Some(item))
-        }
+    fn display_subject(&self, subject: &subject::Subject) -> Result<()> {
+        let ascii_art = utils::utils::subject_ascii_art(
+            &subject.data,
+            &self.settings.font,
+            self.settings.ascii_art_width,
+        )?;
+        display::display_text(&self.display_method, &ascii_art);
+        Ok(())
     }
 
     fn check_meaning(&self, subject: &subject::Subject, answer: &str) -> bool {
-        let correct_meanings: Vec<&str> = match &subject.data {
-            subject::SubjectData::KanaVocabulary(vocab) => {
-                vocab.meanings.iter().map(|m| m.meaning.as_str()).collect()
-            }
-            subject::SubjectData::Radical(radical) => radical
-                .meanings
+        let auxiliary_meanings = |type_: &str| {
+            subject
+                .data
+                .auxiliary_meanings()
                 .iter()
-                .map(|m| m.meaning.as_str())
-                .collect(),
-            subject::SubjectData::Kanji(kanji) => {
-                kanji.meanings.iter().map(|m| m.meaning.as_str()).collect()
-            }
-            subject::SubjectData::Vocabulary(vocab) => {
-                vocab.meanings.iter().map(|m| m.meaning.as_str()).collect()
-            }
+                .filter(|m| m.type_.as_deref() == Some(type_))
+                .map(|m| m.meaning.clone())
+                .collect::<Vec<_>>()
         };
-
-        let normalized_answer = answer.trim().to_lowercase();
-        correct_meanings
+        let mut correct_meanings: Vec<String> = subject
+            .data
+            .meanings()
             .iter()
-            .any(|&m| m.to_lowercase() == normalized_answer)
+            .filter(|m| m.accepted_answer != Some(false))
+            .map(|m| m.meaning.clone())
+            .collect();
+        correct_meanings.extend(auxiliary_meanings("whitelist"));
+        if let Some(study_material) = self.study_materials.get(&subject.id) {
+            correct_meanings.extend(study_material.data.meaning_synonyms.iter().cloned());
+        }
+
+        let max_typos = self
+            .settings
+            .fuzzy_matching
+            .max_typos(utils::utils::normalize_string(answer).chars().count());
+        utils::utils::validate_meaning(
+            answer,
+            &correct_meanings,
+            &auxiliary_meanings("blacklist"),
+            max_typos,
+        )
     }
 
     fn check_reading(&self, subject: &subject::Subject, answer: &str) -> bool {
@@ -257,7 +653,7 @@ impl ReviewSession {
         utils::utils::validate_reading(answer, &correct_readings)
     }
 
-    fn submit_review(&self, item: &ReviewItem) -> Result<()> {
+    fn submit_review(&self, item: &ReviewItem) -> Result<Review> {
         let review_data = serde_json::json!({
             "review": {
                 "assignment_id": item.assignment_id,
@@ -265,8 +661,70 @@ impl ReviewSession {
                 "incorrect_reading_answers": item.incorrect_reading_answers
             }
         });
-        self.client.submit_review(review_data)?;
-        Ok(())
+        self.client.submit_review(review_data)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wanikani::assignment::AssignmentData;
+
+    fn assignment(subject_id: u64) -> Assignment {
+        Assignment {
+            id: subject_id + 1000,
+            object: "assignment".to_string(),
+            url: String::new(),
+            data_updated_at: Utc::now(),
+            data: AssignmentData {
+                created_at: Utc::now(),
+                subject_id,
+                subject_type: "kanji".to_string(),
+                srs_stage: 1,
+                unlocked_at: None,
+                started_at: None,
+                passed_at: None,
+                burned_at: None,
+                available_at: None,
+                resurrected_at: None,
+            },
+        }
+    }
+
+    fn session(subject_count: u64, review_session_size: u16) -> ReviewSession {
+        let assignments: Vec<Assignment> = (1..=subject_count).map(assignment).collect();
+        let settings = Settings {
+            review_session_size,
+            ..Settings::default()
+        };
+        ReviewSession::new(
+            WaniKaniClient::new(String::new(), String::new()),
+            &assignments,
+            "term".to_string(),
+            PathBuf::new(),
+            settings,
+        )
+    }
+
+    #[test]
+    fn test_session_size() {
+        let limited = session(5, 3);
+        assert_eq!(limited.queue, vec![1, 2, 3]);
+        assert_eq!(limited.assignments.len(), 3);
+        assert_eq!(limited.assignments[&1].assignment_id, 1001);
+
+        assert_eq!(session(5, 0).queue.len(), 5);
+        assert_eq!(session(2, 10).queue.len(), 2);
+    }
+
+    #[test]
+    fn test_wrap_up_keeps_started_items() {
+        let mut session = session(5, 0);
+        session.assignments.get_mut(&2).unwrap().started = true;
+        session.assignments.get_mut(&4).unwrap().started = true;
+        session.wrap_up();
+        assert_eq!(session.queue, vec![2, 4]);
+        assert_eq!(session.assignments.len(), 2);
+        assert!(session.assignments.values().all(|item| item.started));
+    }
+}
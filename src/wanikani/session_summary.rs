@@ -0,0 +1,389 @@
+use crate::display;
+use crate::wanikani::srs::{self, SrsSystem};
+use crate::wanikani::subject::subject::Subject;
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Reviews,
+    Lessons,
+    /// Quizzes that are not submitted to WaniKani
+    ExtraStudy,
+}
+
+/// Outcome of one item in a session
+#[derive(Serialize, Debug, Clone)]
+pub struct ItemResult {
+    pub subject_id: u64,
+    pub characters: String,
+    pub meaning: String,
+    pub answered_at: DateTime<Utc>,
+    pub has_reading: bool,
+    pub incorrect_meaning_answers: u32,
+    pub incorrect_reading_answers: u32,
+    /// SRS system the subject follows, which names its stages
+    pub spaced_repetition_system_id: u64,
+    /// SRS stages before and after, as reported by WaniKani, only set for reviews
+    pub starting_srs_stage: Option<u8>,
+    pub ending_srs_stage: Option<u8>,
+}
+
+impl ItemResult {
+    pub fn new(subject: &Subject) -> Self {
+        ItemResult {
+            subject_id: subject.id,
+            characters: subject
+                .data
+                .characters()
+                .unwrap_or(subject.data.slug())
+                .to_string(),
+            meaning: subject
+                .data
+                .meanings()
+                .first()
+                .map(|m| m.meaning.clone())
+                .unwrap_or_default(),
+            answered_at: Utc::now(),
+            has_reading: !subject.data.readings().is_empty(),
+            incorrect_meaning_answers: 0,
+            incorrect_reading_answers: 0,
+            spaced_repetition_system_id: subject.data.spaced_repetition_system_id(),
+            starting_srs_stage: None,
+            ending_srs_stage: None,
+        }
+    }
+
+    pub fn is_correct(&self) -> bool {
+        self.incorrect_meaning_answers == 0 && self.incorrect_reading_answers == 0
+    }
+
+    fn srs_change(&self) -> Option<(u8, u8)> {
+        self.starting_srs_stage.zip(self.ending_srs_stage)
+    }
+
+    fn label(&self) -> String {
+        format!("{} ({})", self.characters, self.meaning)
+    }
+}
+
+/// What happened during a review or lesson session
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionSummary {
+    pub kind: SessionKind,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub items: Vec<ItemResult>,
+    /// Items that were started but not finished before the session ended
+    pub unfinished: usize,
+    /// SRS systems by id, to name the stages items moved between
+    #[serde(skip)]
+    pub srs_systems: HashMap<u64, SrsSystem>,
+}
+
+impl SessionSummary {
+    pub fn new(kind: SessionKind) -> Self {
+        let now = Utc::now();
+        SessionSummary {
+            kind,
+            started_at: now,
+            ended_at: now,
+            items: Vec::new(),
+            unfinished: 0,
+            srs_systems: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, item: ItemResult) {
+        self.items.push(item);
+        self.ended_at = Utc::now();
+    }
+
+    pub fn finish(&mut self, unfinished: usize) {
+        self.unfinished = unfinished;
+        self.ended_at = Utc::now();
+    }
+
+    pub fn elapsed_seconds(&self) -> i64 {
+        (self.ended_at - self.started_at).num_seconds().max(0)
+    }
+
+    pub fn items_per_minute(&self) -> f64 {
+        let minutes = self.elapsed_seconds() as f64 / 60.0;
+        if minutes > 0.0 {
+            self.items.len() as f64 / minutes
+        } else {
+            0.0
+        }
+    }
+
+    /// Share of meaning answers that were correct, `None` if there were none
+    pub fn meaning_accuracy(&self) -> Option<f64> {
+        accuracy(self.items.iter().map(|item| item.incorrect_meaning_answers))
+    }
+
+    /// Share of reading answers that were correct, `None` if there were none
+    pub fn reading_accuracy(&self) -> Option<f64> {
+        accuracy(
+            self.items
+                .iter()
+                .filter(|item| item.has_reading)
+                .map(|item| item.incorrect_reading_answers),
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let title = match self.kind {
+            SessionKind::Reviews => "Review Session Summary",
+            SessionKind::Lessons => "Lesson Session Summary",
+            SessionKind::ExtraStudy => "Extra Study Summary",
+        };
+        let elapsed = self.elapsed_seconds();
+        let mut lines = vec![
+            format!("# {}", title),
+            String::new(),
+            format!(
+                "- Date: {}",
+                self.started_at
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+            ),
+            format!("- Time: {}m {}s", elapsed / 60, elapsed % 60),
+            format!(
+                "- Items: {} ({:.1} per minute)",
+                self.items.len(),
+                self.items_per_minute()
+            ),
+        ];
+        if self.unfinished > 0 {
+            lines.push(format!("- Unfinished: {}", self.unfinished));
+        }
+
+        if self.kind == SessionKind::Lessons {
+            push_section(
+                &mut lines,
+                "Learned",
+                self.items.iter().map(|item| item.label()).collect(),
+            );
+            return lines.join("\n");
+        }
+
+        for (label, accuracy) in [
+            ("Meaning accuracy", self.meaning_accuracy()),
+            ("Reading accuracy", self.reading_accuracy()),
+        ] {
+            if let Some(accuracy) = accuracy {
+                lines.push(format!("- {}: {:.0}%", label, accuracy * 100.0));
+            }
+        }
+
+        push_section(
+            &mut lines,
+            "Correct",
+            self.items
+                .iter()
+                .filter(|item| item.is_correct())
+                .map(|item| item.label())
+                .collect(),
+        );
+        push_section(
+            &mut lines,
+            "Incorrect",
+            self.items
+                .iter()
+                .filter(|item| !item.is_correct())
+                .map(|item| {
+                    format!(
+                        "{}: {} meaning and {} reading mistakes",
+                        item.label(),
+                        item.incorrect_meaning_answers,
+                        item.incorrect_reading_answers
+                    )
+                })
+                .collect(),
+        );
+        for (heading, went_up) in [("Went Up", true), ("Went Down", false)] {
+            push_section(
+                &mut lines,
+                heading,
+                self.items
+                    .iter()
+                    .filter_map(|item| {
+                        let (start, end) = item.srs_change()?;
+                        let changed = if went_up { end > start } else { end < start };
+                        changed.then(|| {
+                            let system = srs::find_system(
+                                &self.srs_systems,
+                                item.spaced_repetition_system_id,
+                            );
+                            format!(
+                                "{}: {} -> {}",
+                                item.label(),
+                                system.stage(start),
+                                system.stage(end)
+                            )
+                        })
+                    })
+                    .collect(),
+            );
+        }
+
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        value["elapsed_seconds"] = self.elapsed_seconds().into();
+        value["items_per_minute"] = self.items_per_minute().into();
+        value["meaning_accuracy"] = self.meaning_accuracy().into();
+        value["reading_accuracy"] = self.reading_accuracy().into();
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+
+    /// Writes the summary to the `sessions` folder of `data_dir`, returns the file written
+    pub fn save(&self, data_dir: &Path, json: bool) -> Result<PathBuf> {
+        let dir = data_dir.join("sessions");
+        std::fs::create_dir_all(&dir)?;
+        let kind = match self.kind {
+            SessionKind::Reviews => "reviews",
+            SessionKind::Lessons => "lessons",
+            SessionKind::ExtraStudy => "extra-study",
+        };
+        let file_name = format!(
+            "{}-{}.{}",
+            kind,
+            self.started_at
+                .with_timezone(&Local)
+                .format("%Y%m%d-%H%M%S"),
+            if json { "json" } else { "md" }
+        );
+        let path = dir.join(file_name);
+        let contents = if json {
+            self.to_json()?
+        } else {
+            self.to_markdown()
+        };
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+/// Correct answers divided by all answers, each finished item has exactly one correct answer
+fn accuracy(incorrect_answers: impl Iterator<Item = u32>) -> Option<f64> {
+    let (correct, incorrect) = incorrect_answers.fold((0, 0), |(correct, incorrect), n| {
+        (correct + 1, incorrect + n)
+    });
+    (correct > 0).then(|| correct as f64 / (correct + incorrect) as f64)
+}
+
+fn push_section(lines: &mut Vec<String>, heading: &str, items: Vec<String>) {
+    if items.is_empty() {
+        return;
+    }
+    lines.push(String::new());
+    lines.push(format!("## {} ({})", heading, items.len()));
+    lines.extend(items.into_iter().map(|item| format!("- {}", item)));
+}
+
+/// Shows the summary of a finished session and offers to save it
+pub fn show_summary(output_method: &str, summary: &SessionSummary, data_dir: &Path) {
+    if summary.items.is_empty() {
+        return;
+    }
+    display::display_text(output_method, &summary.to_markdown());
+
+    let choice = display::text_input(
+        output_method,
+        "Enter 'm' to save this summary as Markdown, 'j' as JSON, or press Enter to continue:",
+    );
+    let json = match choice.as_str() {
+        "m" => false,
+        "j" => true,
+        _ => return,
+    };
+    match summary.save(data_dir, json) {
+        Ok(path) => display::display_text(
+            output_method,
+            &format!("Saved the summary to {}", path.display()),
+        ),
+        Err(e) => {
+            display::display_text(output_method, &format!("Failed to save the summary: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wanikani::spaced_repetition_system::SpacedRepetitionSystemData;
+    use chrono::TimeZone;
+
+    fn item(id: u64, incorrect_meaning: u32, incorrect_reading: u32, srs: (u8, u8)) -> ItemResult {
+        ItemResult {
+            subject_id: id,
+            characters: "大".to_string(),
+            meaning: "Big".to_string(),
+            answered_at: Utc::now(),
+            has_reading: true,
+            incorrect_meaning_answers: incorrect_meaning,
+            incorrect_reading_answers: incorrect_reading,
+            spaced_repetition_system_id: 1,
+            starting_srs_stage: Some(srs.0),
+            ending_srs_stage: Some(srs.1),
+        }
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let mut summary = SessionSummary::new(SessionKind::Reviews);
+        assert_eq!(summary.meaning_accuracy(), None);
+
+        summary.add(item(1, 0, 0, (1, 2)));
+        summary.add(item(2, 2, 1, (5, 3)));
+        assert_eq!(summary.meaning_accuracy(), Some(0.5));
+        assert_eq!(summary.reading_accuracy(), Some(2.0 / 3.0));
+    }
+
+    #[test]
+    fn test_markdown() {
+        let mut summary = SessionSummary::new(SessionKind::Reviews);
+        summary.add(item(1, 0, 0, (1, 2)));
+        summary.add(item(2, 1, 0, (5, 3)));
+        let markdown = summary.to_markdown();
+
+        assert!(markdown.starts_with("# Review Session Summary\n"));
+        assert!(markdown.contains("- Meaning accuracy: 67%\n- Reading accuracy: 100%"));
+        assert!(markdown.contains("## Correct (1)\n- 大 (Big)\n"));
+        assert!(markdown.contains("## Went Down (1)\n- 大 (Big): Guru I -> Apprentice III"));
+    }
+
+    #[test]
+    fn test_markdown_uses_the_subjects_srs_system() {
+        let mut summary = SessionSummary::new(SessionKind::Reviews);
+        let short = SpacedRepetitionSystemData {
+            created_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            name: "Short".to_string(),
+            description: String::new(),
+            unlocking_stage_position: 0,
+            starting_stage_position: 1,
+            passing_stage_position: 3,
+            burning_stage_position: 9,
+            stages: Vec::new(),
+        };
+        summary.srs_systems = HashMap::from([(2, SrsSystem::new(&short))]);
+        summary.add(ItemResult {
+            spaced_repetition_system_id: 2,
+            ..item(1, 0, 0, (2, 3))
+        });
+        summary.add(item(2, 0, 0, (2, 3)));
+        let markdown = summary.to_markdown();
+
+        assert!(markdown.contains(
+            "## Went Up (2)\n- 大 (Big): Apprentice II -> Guru I\n- 大 (Big): Apprentice II -> Apprentice III"
+        ));
+    }
+}
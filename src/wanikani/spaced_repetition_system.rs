@@ -0,0 +1,37 @@
+use crate::wanikani::decode::from_rfc3339;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The stages a subject goes through and how long each one waits, subjects name theirs by id
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpacedRepetitionSystem {
+    pub id: u64,
+    pub object: String,
+    pub url: String,
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub data_updated_at: DateTime<Utc>,
+    pub data: SpacedRepetitionSystemData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpacedRepetitionSystemData {
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub created_at: DateTime<Utc>,
+    pub name: String,
+    pub description: String,
+    pub unlocking_stage_position: u8,
+    pub starting_stage_position: u8,
+    pub passing_stage_position: u8,
+    pub burning_stage_position: u8,
+    pub stages: Vec<SpacedRepetitionStage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpacedRepetitionStage {
+    /// Not set for the unlocking and burning stages
+    pub interval: Option<u64>,
+    /// "milliseconds", "seconds", "minutes", "hours", "days" or "weeks"
+    pub interval_unit: Option<String>,
+    pub position: u8,
+}
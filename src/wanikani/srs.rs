@@ -0,0 +1,263 @@
+use crate::wanikani::spaced_repetition_system::SpacedRepetitionSystemData;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The groups WaniKani shows stages in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SrsGroup {
+    Lesson,
+    Apprentice,
+    Guru,
+    Master,
+    Enlightened,
+    Burned,
+}
+
+impl SrsGroup {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SrsGroup::Lesson => "Lesson",
+            SrsGroup::Apprentice => "Apprentice",
+            SrsGroup::Guru => "Guru",
+            SrsGroup::Master => "Master",
+            SrsGroup::Enlightened => "Enlightened",
+            SrsGroup::Burned => "Burned",
+        }
+    }
+}
+
+/// One stage of an SRS system, shown like "Apprentice III" or "Master"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SrsStage {
+    pub position: u8,
+    pub group: SrsGroup,
+    /// Number within the group starting at 1, `None` if the group has only one stage
+    pub number: Option<u8>,
+    /// Time until the next review, `None` for lessons and burned items
+    pub interval: Option<Duration>,
+}
+
+impl fmt::Display for SrsStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.number {
+            Some(number) => write!(f, "{} {}", self.group.name(), roman_numeral(number)),
+            None => write!(f, "{}", self.group.name()),
+        }
+    }
+}
+
+fn roman_numeral(number: u8) -> String {
+    const NUMERALS: [(u8, &str); 6] = [
+        (50, "L"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut rest = number;
+    let mut numeral = String::new();
+    for (value, symbol) in NUMERALS {
+        while rest >= value {
+            numeral.push_str(symbol);
+            rest -= value;
+        }
+    }
+    numeral
+}
+
+fn interval(amount: u64, unit: &str) -> Option<Duration> {
+    let amount = amount as i64;
+    match unit {
+        "milliseconds" => Some(Duration::milliseconds(amount)),
+        "seconds" => Some(Duration::seconds(amount)),
+        "minutes" => Some(Duration::minutes(amount)),
+        "hours" => Some(Duration::hours(amount)),
+        "days" => Some(Duration::days(amount)),
+        "weeks" => Some(Duration::weeks(amount)),
+        _ => None,
+    }
+}
+
+/// The stages of a spaced repetition system and how items move between them
+#[derive(Debug, Clone)]
+pub struct SrsSystem {
+    starting_position: u8,
+    passing_position: u8,
+    burning_position: u8,
+    /// Indexed by stage position
+    intervals: Vec<Option<Duration>>,
+}
+
+impl SrsSystem {
+    pub fn new(data: &SpacedRepetitionSystemData) -> Self {
+        let mut intervals = vec![None; data.burning_stage_position as usize + 1];
+        for stage in &data.stages {
+            if let (Some(slot), Some(amount), Some(unit)) = (
+                intervals.get_mut(stage.position as usize),
+                stage.interval,
+                &stage.interval_unit,
+            ) {
+                *slot = interval(amount, unit);
+            }
+        }
+        SrsSystem {
+            starting_position: data.starting_stage_position,
+            passing_position: data.passing_stage_position,
+            burning_position: data.burning_stage_position,
+            intervals,
+        }
+    }
+
+    /// WaniKani's default system, used when the real one is not known
+    pub fn standard() -> Self {
+        let hours = [4, 8, 23, 47, 167, 335, 719, 2879];
+        let mut intervals = vec![None];
+        intervals.extend(hours.iter().map(|h| Some(Duration::hours(*h))));
+        intervals.push(None);
+        SrsSystem {
+            starting_position: 1,
+            passing_position: 5,
+            burning_position: 9,
+            intervals,
+        }
+    }
+
+    pub fn stage(&self, position: u8) -> SrsStage {
+        let position = position.min(self.burning_position);
+        let master = self.burning_position.saturating_sub(2);
+        let (group, first, count) = if position < self.starting_position {
+            (SrsGroup::Lesson, position, 1)
+        } else if position < self.passing_position {
+            let count = self.passing_position - self.starting_position;
+            (SrsGroup::Apprentice, self.starting_position, count)
+        } else if position < master {
+            (
+                SrsGroup::Guru,
+                self.passing_position,
+                master - self.passing_position,
+            )
+        } else if position == master {
+            (SrsGroup::Master, position, 1)
+        } else if position < self.burning_position {
+            (SrsGroup::Enlightened, position, 1)
+        } else {
+            (SrsGroup::Burned, position, 1)
+        };
+        SrsStage {
+            position,
+            group,
+            number: (count > 1).then_some(position - first + 1),
+            interval: self.intervals.get(position as usize).copied().flatten(),
+        }
+    }
+
+    /// Stage an item moves to after a review, following WaniKani's rules: one stage up
+    /// when answered without mistakes, otherwise down by one stage per two incorrect
+    /// answers, twice as fast once passed, but never below the starting stage
+    pub fn next_stage(&self, position: u8, incorrect_answers: u32) -> SrsStage {
+        if incorrect_answers == 0 {
+            return self.stage((position + 1).min(self.burning_position));
+        }
+        let penalty_factor = if position >= self.passing_position {
+            2
+        } else {
+            1
+        };
+        let adjustment = incorrect_answers.div_ceil(2) * penalty_factor;
+        let next = (position as u32)
+            .saturating_sub(adjustment)
+            .max(self.starting_position as u32);
+        self.stage(next as u8)
+    }
+
+    /// When an item answered at `answered_at` comes up for review again, `None` once burned
+    /// WaniKani rounds review times down to the hour
+    pub fn next_review_at(
+        &self,
+        position: u8,
+        incorrect_answers: u32,
+        answered_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let interval = self.next_stage(position, incorrect_answers).interval?;
+        let available_at = answered_at + interval;
+        Some(
+            available_at
+                .duration_trunc(Duration::hours(1))
+                .unwrap_or(available_at),
+        )
+    }
+}
+
+/// The system with the given id, WaniKani's default if it is not known
+pub fn find_system(systems: &HashMap<u64, SrsSystem>, id: u64) -> SrsSystem {
+    systems
+        .get(&id)
+        .cloned()
+        .unwrap_or_else(SrsSystem::standard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_stage_name() {
+        let stage_name = |stage| SrsSystem::standard().stage(stage).to_string();
+        assert_eq!(stage_name(0), "Lesson");
+        assert_eq!(stage_name(4), "Apprentice IV");
+        assert_eq!(stage_name(6), "Guru II");
+        assert_eq!(stage_name(7), "Master");
+        assert_eq!(stage_name(9), "Burned");
+    }
+
+    #[test]
+    fn test_next_stage() {
+        let system = SrsSystem::standard();
+        let next = |stage, incorrect| system.next_stage(stage, incorrect).position;
+        assert_eq!(next(1, 0), 2);
+        assert_eq!(next(9, 0), 9);
+        assert_eq!(next(4, 1), 3);
+        assert_eq!(next(4, 3), 2);
+        assert_eq!(next(6, 1), 4);
+        assert_eq!(next(2, 5), 1);
+    }
+
+    #[test]
+    fn test_next_review_at() {
+        let system = SrsSystem::standard();
+        let answered_at = Utc.with_ymd_and_hms(2024, 1, 1, 10, 30, 0).unwrap();
+        assert_eq!(
+            system.next_review_at(1, 0, answered_at),
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 18, 0, 0).unwrap())
+        );
+        assert_eq!(system.next_review_at(8, 0, answered_at), None);
+    }
+
+    #[test]
+    fn test_system_from_api() {
+        let data: SpacedRepetitionSystemData = serde_json::from_value(serde_json::json!({
+            "created_at": "2020-05-21T20:46:06Z",
+            "name": "Accelerated",
+            "description": "Used for the first two levels",
+            "unlocking_stage_position": 0,
+            "starting_stage_position": 1,
+            "passing_stage_position": 5,
+            "burning_stage_position": 9,
+            "stages": [
+                {"interval": null, "interval_unit": null, "position": 0},
+                {"interval": 7200, "interval_unit": "seconds", "position": 1},
+                {"interval": 1, "interval_unit": "weeks", "position": 5},
+                {"interval": null, "interval_unit": null, "position": 9}
+            ]
+        }))
+        .unwrap();
+        let system = SrsSystem::new(&data);
+        assert_eq!(system.stage(1).interval, Some(Duration::hours(2)));
+        assert_eq!(system.stage(5).to_string(), "Guru I");
+        assert_eq!(system.stage(5).interval, Some(Duration::weeks(1)));
+        assert_eq!(system.stage(9).interval, None);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::display;
+use crate::wanikani::cache::{ReviewStatisticCache, SubjectCache};
+use crate::wanikani::review_statistic::{ReviewStatisticData, ReviewStatisticFilters};
+use crate::WaniKaniClient;
+use anyhow::Result;
+use std::path::Path;
+
+/// How many items are listed below a chosen percentage at most
+const MAX_LISTED: usize = 20;
+
+const SUBJECT_TYPES: [(&str, &str); 4] = [
+    ("radical", "Radicals"),
+    ("kanji", "Kanji"),
+    ("vocabulary", "Vocabulary"),
+    ("kana_vocabulary", "Kana Vocabulary"),
+];
+
+/// Correct answers divided by all answers, `None` if there were none
+fn accuracy(correct: u32, incorrect: u32) -> Option<f64> {
+    let total = correct + incorrect;
+    (total > 0).then(|| correct as f64 / total as f64)
+}
+
+/// Overall accuracy and a breakdown per subject type
+pub fn overview(stats: &[&ReviewStatisticData]) -> String {
+    let sum = |f: fn(&ReviewStatisticData) -> u32| stats.iter().map(|stat| f(stat)).sum::<u32>();
+    let mut lines = vec![format!("Review Statistics ({} items)", stats.len())];
+    for (label, accuracy) in [
+        (
+            "Meaning accuracy",
+            accuracy(sum(|s| s.meaning_correct), sum(|s| s.meaning_incorrect)),
+        ),
+        (
+            "Reading accuracy",
+            accuracy(sum(|s| s.reading_correct), sum(|s| s.reading_incorrect)),
+        ),
+    ] {
+        if let Some(accuracy) = accuracy {
+            lines.push(format!("{}: {:.0}%", label, accuracy * 100.0));
+        }
+    }
+
+    for (type_, title) in SUBJECT_TYPES {
+        let of_type: Vec<u32> = stats
+            .iter()
+            .filter(|stat| stat.subject_type == type_)
+            .map(|stat| stat.percentage_correct as u32)
+            .collect();
+        if of_type.is_empty() {
+            continue;
+        }
+        let average = of_type.iter().sum::<u32>() as f64 / of_type.len() as f64;
+        let below = of_type
+            .iter()
+            .filter(|percentage| **percentage < 80)
+            .count();
+        lines.push(format!(
+            "{}: {} items, {:.0}% correct on average, {} below 80%",
+            title,
+            of_type.len(),
+            average,
+            below
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Shows the user's review statistics and lists the items they get wrong most
+pub fn show_stats(output_method: &str, client: &WaniKaniClient, cache_dir: &Path) -> Result<()> {
+    let mut statistics = ReviewStatisticCache::load(cache_dir)?;
+    if let Err(e) = statistics.refresh(client) {
+        if statistics.is_empty() {
+            return Err(e);
+        }
+        display::display_text(
+            output_method,
+            &format!(
+                "Could not update the statistics, showing the saved ones: {}",
+                e
+            ),
+        );
+    }
+
+    let visible = ReviewStatisticFilters {
+        hidden: Some(false),
+        ..Default::default()
+    };
+    let stats: Vec<&ReviewStatisticData> = statistics
+        .matching(&visible)
+        .map(|statistic| &statistic.data)
+        .collect();
+    if stats.is_empty() {
+        display::display_text(
+            output_method,
+            "There are no statistics yet, they start with your first review.",
+        );
+        return Ok(());
+    }
+    display::display_text(output_method, &overview(&stats));
+
+    loop {
+        let input = display::text_input(
+            output_method,
+            "Enter a percentage to list the items answered correctly less often, or press Enter to go back:",
+        );
+        if input.is_empty() || input == "q" {
+            return Ok(());
+        }
+        let percentage = match input.parse::<u8>() {
+            Ok(percentage) if percentage <= 100 => percentage,
+            _ => {
+                display::display_text(output_method, "Please enter a number from 0 to 100.");
+                continue;
+            }
+        };
+
+        let filters = ReviewStatisticFilters {
+            percentages_less_than: Some(percentage),
+            ..visible.clone()
+        };
+        let mut below: Vec<&ReviewStatisticData> = statistics
+            .matching(&filters)
+            .map(|statistic| &statistic.data)
+            .collect();
+        if below.is_empty() {
+            display::display_text(
+                output_method,
+                &format!("All of your items are at {}% or better.", percentage),
+            );
+            continue;
+        }
+        below.sort_by_key(|stat| stat.percentage_correct);
+
+        let subject_ids: Vec<u64> = below
+            .iter()
+            .take(MAX_LISTED)
+            .map(|stat| stat.subject_id)
+            .collect();
+        let mut cache = SubjectCache::load(cache_dir)?;
+        cache.ensure(client, &subject_ids)?;
+
+        let mut listing = below
+            .iter()
+            .take(MAX_LISTED)
+            .map(|stat| {
+                let label = cache
+                    .get(stat.subject_id)
+                    .map(|subject| {
+                        subject
+                            .data
+                            .characters()
+                            .unwrap_or(subject.data.slug())
+                            .to_string()
+                    })
+                    .unwrap_or_else(|| stat.subject_id.to_string());
+                format!("{:>3}% {}", stat.percentage_correct, label)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if below.len() > MAX_LISTED {
+            listing.push_str(&format!("\n... and {} more", below.len() - MAX_LISTED));
+        }
+        display::display_text(output_method, &listing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(
+        subject_type: &str,
+        percentage_correct: u8,
+        meaning: (u32, u32),
+    ) -> ReviewStatisticData {
+        ReviewStatisticData {
+            meaning_correct: meaning.0,
+            meaning_incorrect: meaning.1,
+            percentage_correct,
+            reading_correct: 0,
+            subject_type: subject_type.to_string(),
+            ..ReviewStatisticData::sample(1)
+        }
+    }
+
+    #[test]
+    fn test_overview() {
+        let stats = [
+            stat("radical", 100, (3, 0)),
+            stat("kanji", 90, (2, 1)),
+            stat("kanji", 60, (1, 1)),
+        ];
+        let refs: Vec<&ReviewStatisticData> = stats.iter().collect();
+        assert_eq!(
+            overview(&refs),
+            "Review Statistics (3 items)\n\
+             Meaning accuracy: 75%\n\
+             Radicals: 1 items, 100% correct on average, 0 below 80%\n\
+             Kanji: 2 items, 75% correct on average, 1 below 80%"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Reads a JSON file kept between runs, `None` if it does not exist yet
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = fs::read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&json)?))
+}
+
+/// Reads a cache file, starting over with an empty cache if it cannot be parsed
+/// A cache is rebuilt from WaniKani, so a corrupt or outdated file only costs a download
+pub fn load_cache<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    match load_json(path) {
+        Ok(cache) => Ok(cache.unwrap_or_default()),
+        Err(e) if e.is::<serde_json::Error>() => {
+            eprintln!(
+                "Warning: {} could not be read and will be rebuilt: {}",
+                path.display(),
+                e
+            );
+            Ok(T::default())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes a JSON file atomically: a temporary file next to it is renamed over it,
+/// so an interrupted write never leaves a half written file behind
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(serde_json::to_string(value)?.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = std::env::temp_dir().join(format!("kanikani-test-store-{}", std::process::id()));
+        let path = dir.join("store.json");
+        assert_eq!(load_json::<BTreeMap<u64, String>>(&path).unwrap(), None);
+
+        let value = BTreeMap::from([(1, "one".to_string())]);
+        save_json(&path, &value).unwrap();
+        assert_eq!(load_json(&path).unwrap(), Some(value));
+        assert!(!path.with_extension("json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_cache_starts_over() {
+        let dir = std::env::temp_dir().join(format!("kanikani-test-cache-{}", std::process::id()));
+        let path = dir.join("cache.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "{ not json").unwrap();
+        assert!(load_json::<BTreeMap<u64, String>>(&path).is_err());
+        assert_eq!(
+            load_cache::<BTreeMap<u64, String>>(&path).unwrap(),
+            BTreeMap::new()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
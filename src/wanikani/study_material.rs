@@ -0,0 +1,79 @@
+use crate::wanikani::decode::from_rfc3339;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The user's own notes and synonyms for a subject
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StudyMaterial {
+    pub id: u64,
+    pub object: String,
+    pub url: String,
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub data_updated_at: DateTime<Utc>,
+    pub data: StudyMaterialData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StudyMaterialData {
+    #[serde(deserialize_with = "from_rfc3339")]
+    pub created_at: DateTime<Utc>,
+    pub hidden: bool,
+    pub meaning_note: Option<String>,
+    pub meaning_synonyms: Vec<String>,
+    pub reading_note: Option<String>,
+    pub subject_id: u64,
+    pub subject_type: String,
+}
+
+/// WaniKani accepts at most this many synonyms per subject
+pub const MAX_SYNONYMS: usize = 8;
+
+/// Fields to change when creating or updating study materials, unset fields are left as they are
+/// A note set to `Some(None)` is removed
+#[derive(Serialize, Debug, Default)]
+pub struct StudyMaterialUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meaning_note: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reading_note: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meaning_synonyms: Option<Vec<String>>,
+}
+
+/// Adds a synonym to a list of synonyms, refusing duplicates and going over the limit
+pub fn add_synonym(synonyms: &[String], synonym: &str) -> Result<Vec<String>, String> {
+    let synonym = synonym.trim();
+    if synonym.is_empty() {
+        return Err("A synonym cannot be empty.".to_string());
+    }
+    if synonyms.iter().any(|s| s.eq_ignore_ascii_case(synonym)) {
+        return Err(format!("\"{}\" is already one of your synonyms.", synonym));
+    }
+    if synonyms.len() >= MAX_SYNONYMS {
+        return Err(format!(
+            "You already have {} synonyms, the most WaniKani allows.",
+            MAX_SYNONYMS
+        ));
+    }
+
+    let mut synonyms = synonyms.to_vec();
+    synonyms.push(synonym.to_string());
+    Ok(synonyms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_synonym() {
+        let synonyms = add_synonym(&[], " doggo ").unwrap();
+        assert_eq!(synonyms, vec!["doggo".to_string()]);
+        assert!(add_synonym(&synonyms, "Doggo").is_err());
+        assert!(add_synonym(&synonyms, "  ").is_err());
+
+        let full: Vec<String> = (0..MAX_SYNONYMS).map(|i| i.to_string()).collect();
+        assert!(add_synonym(&full, "pupper").is_err());
+    }
+}
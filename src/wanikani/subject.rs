@@ -3,7 +3,7 @@ pub mod subject {
     use chrono::{DateTime, Utc};
     use serde::{Deserialize, Serialize};
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct Subject {
         pub id: u64,
         pub url: String,
@@ -13,7 +13,7 @@ pub mod subject {
         pub data: SubjectData,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     #[serde(tag = "object", rename_all = "lowercase", content = "data")]
     pub enum SubjectData {
         Radical(RadicalData),
@@ -22,7 +22,7 @@ pub mod subject {
         KanaVocabulary(KanaVocabularyData),
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct RadicalData {
         pub amalgamation_subject_ids: Vec<u64>,
         pub auxiliary_meanings: Vec<Meaning>,
@@ -41,7 +41,7 @@ pub mod subject {
         pub spaced_repetition_system_id: u8,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct KanjiData {
         pub amalgamation_subject_ids: Vec<u64>,
         pub auxiliary_meanings: Vec<Meaning>,
@@ -65,7 +65,7 @@ pub mod subject {
         pub spaced_repetition_system_id: u8,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct VocabularyData {
         pub auxiliary_meanings: Vec<Meaning>,
         pub characters: String,
@@ -88,7 +88,7 @@ pub mod subject {
         pub spaced_repetition_system_id: u8,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct KanaVocabularyData {
         #[serde(deserialize_with = "from_rfc3339")]
         pub created_at: DateTime<Utc>,
@@ -108,20 +108,20 @@ pub mod subject {
         pub spaced_repetition_system_id: u8,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct ContextSentence {
         pub en: String,
         pub ja: String,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct PronunciationAudio {
         pub url: String,
         pub metadata: PronunciationAudioMetadata,
         pub content_type: String,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct PronunciationAudioMetadata {
         gender: String,
         source_id: u64,
@@ -131,14 +131,14 @@ pub mod subject {
         voice_description: String,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct CharacterImage {
         pub url: String,
         pub content_type: String,
         pub metadata: CharacterImageMetadata,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct CharacterImageMetadata {
         pub inline_styles: Option<bool>,
         pub color: Option<String>,
@@ -146,14 +146,18 @@ pub mod subject {
         pub style_name: Option<String>,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct Meaning {
         pub meaning: String,
         pub primary: Option<bool>,
         pub accepted_answer: Option<bool>,
+
+        /// Only set on auxiliary meanings: either "whitelist" or "blacklist"
+        #[serde(rename = "type")]
+        pub type_: Option<String>,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct Reading {
         pub reading: String,
         pub primary: bool,
@@ -162,4 +166,154 @@ pub mod subject {
         #[serde(rename = "type")]
         pub type_: Option<String>,
     }
+
+    impl SubjectData {
+        /// Human readable name of the subject type
+        pub fn type_name(&self) -> &'static str {
+            match self {
+                SubjectData::Radical(_) => "Radical",
+                SubjectData::Kanji(_) => "Kanji",
+                SubjectData::Vocabulary(_) => "Vocabulary",
+                SubjectData::KanaVocabulary(_) => "Kana Vocabulary",
+            }
+        }
+
+        /// Characters of the subject, `None` for image-only radicals
+        pub fn characters(&self) -> Option<&str> {
+            match self {
+                SubjectData::Radical(radical) => radical.characters.as_deref(),
+                SubjectData::Kanji(kanji) => Some(&kanji.characters),
+                SubjectData::Vocabulary(vocab) => Some(&vocab.characters),
+                SubjectData::KanaVocabulary(vocab) => Some(&vocab.characters),
+            }
+        }
+
+        pub fn slug(&self) -> &str {
+            match self {
+                SubjectData::Radical(radical) => &radical.slug,
+                SubjectData::Kanji(kanji) => &kanji.slug,
+                SubjectData::Vocabulary(vocab) => &vocab.slug,
+                SubjectData::KanaVocabulary(vocab) => &vocab.slug,
+            }
+        }
+
+        pub fn level(&self) -> u8 {
+            match self {
+                SubjectData::Radical(radical) => radical.level,
+                SubjectData::Kanji(kanji) => kanji.level,
+                SubjectData::Vocabulary(vocab) => vocab.level,
+                SubjectData::KanaVocabulary(vocab) => vocab.level,
+            }
+        }
+
+        pub fn spaced_repetition_system_id(&self) -> u64 {
+            match self {
+                SubjectData::Radical(radical) => radical.spaced_repetition_system_id as u64,
+                SubjectData::Kanji(kanji) => kanji.spaced_repetition_system_id as u64,
+                SubjectData::Vocabulary(vocab) => vocab.spaced_repetition_system_id as u64,
+                SubjectData::KanaVocabulary(vocab) => vocab.spaced_repetition_system_id as u64,
+            }
+        }
+
+        pub fn meanings(&self) -> &[Meaning] {
+            match self {
+                SubjectData::Radical(radical) => &radical.meanings,
+                SubjectData::Kanji(kanji) => &kanji.meanings,
+                SubjectData::Vocabulary(vocab) => &vocab.meanings,
+                SubjectData::KanaVocabulary(vocab) => &vocab.meanings,
+            }
+        }
+
+        pub fn auxiliary_meanings(&self) -> &[Meaning] {
+            match self {
+                SubjectData::Radical(radical) => &radical.auxiliary_meanings,
+                SubjectData::Kanji(kanji) => &kanji.auxiliary_meanings,
+                SubjectData::Vocabulary(vocab) => &vocab.auxiliary_meanings,
+                SubjectData::KanaVocabulary(vocab) => &vocab.auxiliary_meanings,
+            }
+        }
+
+        /// Readings of the subject, empty for radicals and kana vocabulary
+        pub fn readings(&self) -> &[Reading] {
+            match self {
+                SubjectData::Kanji(kanji) => &kanji.readings,
+                SubjectData::Vocabulary(vocab) => &vocab.readings,
+                SubjectData::Radical(_) | SubjectData::KanaVocabulary(_) => &[],
+            }
+        }
+
+        /// Readings of one type: "onyomi", "kunyomi" or "nanori" for kanji
+        pub fn readings_of_type(&self, type_: &str) -> Vec<&Reading> {
+            self.readings()
+                .iter()
+                .filter(|r| r.type_.as_deref() == Some(type_))
+                .collect()
+        }
+
+        pub fn meaning_mnemonic(&self) -> &str {
+            match self {
+                SubjectData::Radical(radical) => &radical.meaning_mnemonic,
+                SubjectData::Kanji(kanji) => &kanji.meaning_mnemonic,
+                SubjectData::Vocabulary(vocab) => &vocab.meaning_mnemonic,
+                SubjectData::KanaVocabulary(vocab) => &vocab.meaning_mnemonic,
+            }
+        }
+
+        pub fn reading_mnemonic(&self) -> Option<&str> {
+            match self {
+                SubjectData::Kanji(kanji) => Some(&kanji.reading_mnemonic),
+                SubjectData::Vocabulary(vocab) => Some(&vocab.reading_mnemonic),
+                SubjectData::Radical(_) | SubjectData::KanaVocabulary(_) => None,
+            }
+        }
+
+        pub fn context_sentences(&self) -> &[ContextSentence] {
+            match self {
+                SubjectData::Vocabulary(vocab) => &vocab.context_sentences,
+                SubjectData::KanaVocabulary(vocab) => &vocab.context_sentences,
+                SubjectData::Radical(_) | SubjectData::Kanji(_) => &[],
+            }
+        }
+
+        /// Subjects this one is built from: radicals for kanji, kanji for vocabulary
+        pub fn component_subject_ids(&self) -> &[u64] {
+            match self {
+                SubjectData::Kanji(kanji) => &kanji.component_subject_ids,
+                SubjectData::Vocabulary(vocab) => &vocab.component_subject_ids,
+                SubjectData::Radical(_) | SubjectData::KanaVocabulary(_) => &[],
+            }
+        }
+
+        /// Subjects this one is used in: kanji for radicals, vocabulary for kanji
+        pub fn amalgamation_subject_ids(&self) -> &[u64] {
+            match self {
+                SubjectData::Radical(radical) => &radical.amalgamation_subject_ids,
+                SubjectData::Kanji(kanji) => &kanji.amalgamation_subject_ids,
+                SubjectData::Vocabulary(_) | SubjectData::KanaVocabulary(_) => &[],
+            }
+        }
+
+        pub fn visually_similar_subject_ids(&self) -> &[u64] {
+            match self {
+                SubjectData::Kanji(kanji) => &kanji.visually_similar_subject_ids,
+                _ => &[],
+            }
+        }
+
+        pub fn pronunciation_audios(&self) -> &[PronunciationAudio] {
+            match self {
+                SubjectData::Vocabulary(vocab) => &vocab.pronunciation_audios,
+                SubjectData::KanaVocabulary(vocab) => &vocab.pronunciation_audios,
+                SubjectData::Radical(_) | SubjectData::Kanji(_) => &[],
+            }
+        }
+
+        pub fn parts_of_speech(&self) -> &[String] {
+            match self {
+                SubjectData::Vocabulary(vocab) => &vocab.parts_of_speech,
+                SubjectData::KanaVocabulary(vocab) => &vocab.parts_of_speech,
+                SubjectData::Radical(_) | SubjectData::Kanji(_) => &[],
+            }
+        }
+    }
 }
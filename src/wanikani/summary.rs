@@ -33,15 +33,56 @@ impl Summary {
         self.data
             .lessons
             .iter()
+            .filter(|block| block.available_at <= Utc::now())
             .flat_map(|block| block.subject_ids.clone())
             .collect()
     }
 
+    /// Reviews available right now, the summary also lists those of the next 24 hours
     pub fn get_available_reviews(&self) -> Vec<u64> {
         self.data
             .reviews
             .iter()
+            .filter(|block| block.available_at <= Utc::now())
             .flat_map(|block| block.subject_ids.clone())
             .collect()
     }
+
+    /// Number of reviews becoming available at each upcoming hour
+    pub fn forecast(&self, now: DateTime<Utc>) -> Vec<(DateTime<Utc>, usize)> {
+        self.data
+            .reviews
+            .iter()
+            .filter(|block| block.available_at > now && !block.subject_ids.is_empty())
+            .map(|block| (block.available_at, block.subject_ids.len()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_available_reviews_and_forecast() {
+        let now = Utc::now();
+        let block = |hours: i64, subject_ids: Vec<u64>| ReviewBlock {
+            available_at: now + Duration::hours(hours),
+            subject_ids,
+        };
+        let summary = Summary {
+            object: "report".to_string(),
+            url: String::new(),
+            data_updated_at: now,
+            data: SummaryData {
+                lessons: vec![],
+                reviews: vec![block(-1, vec![1, 2]), block(1, vec![]), block(2, vec![3])],
+                next_reviews_at: None,
+            },
+        };
+
+        assert_eq!(summary.get_available_reviews(), vec![1, 2]);
+        assert_eq!(summary.forecast(now), vec![(now + Duration::hours(2), 1)]);
+    }
 }
@@ -0,0 +1,209 @@
+use crate::display;
+use crate::wanikani::level_progression::LevelProgressionData;
+use crate::WaniKaniClient;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Utc};
+
+const MAX_LEVEL: u8 = 60;
+
+/// The current pace is the median time of this many most recently passed levels
+const PACE_LEVELS: usize = 5;
+
+/// Time from unlocking a level to passing it
+pub fn level_duration(progression: &LevelProgressionData) -> Option<Duration> {
+    let unlocked_at = progression.unlocked_at.or(progression.started_at)?;
+    Some(progression.passed_at? - unlocked_at)
+}
+
+pub fn median(durations: &[Duration]) -> Option<Duration> {
+    let mut sorted = durations.to_vec();
+    sorted.sort();
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((sorted[middle - 1] + sorted[middle]) / 2),
+        _ => Some(sorted[middle]),
+    }
+}
+
+pub fn average(durations: &[Duration]) -> Option<Duration> {
+    let total = durations
+        .iter()
+        .fold(Duration::zero(), |total, d| total + *d);
+    (!durations.is_empty()).then(|| total / durations.len() as i32)
+}
+
+/// When level 60 will be unlocked if every remaining level takes `pace`
+/// A current level that is already taking longer than `pace` is assumed to be passed now
+pub fn project_max_level(
+    current: &LevelProgressionData,
+    pace: Duration,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let remaining = MAX_LEVEL.checked_sub(current.level).filter(|n| *n > 0)?;
+    let unlocked_at = current.unlocked_at.or(current.started_at)?;
+    let current_passed = (unlocked_at + pace).max(now);
+    Some(current_passed + pace * (remaining as i32 - 1))
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    format!("{}d {}h", duration.num_days(), duration.num_hours() % 24)
+}
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+    date.map(|date| date.with_timezone(&Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// One line per level and the statistics below it
+pub fn timeline_text(progressions: &[LevelProgressionData], now: DateTime<Utc>) -> String {
+    let mut lines = vec![format!(
+        "{:<6} {:<10}  {:<10}  {:<10}  {:<10}  {}",
+        "Level", "Unlocked", "Started", "Passed", "Completed", "Time"
+    )];
+    for progression in progressions {
+        let time = match level_duration(progression) {
+            Some(duration) => format_duration(duration),
+            // Still on this level
+            None => progression
+                .unlocked_at
+                .map(|unlocked_at| format!("{} so far", format_duration(now - unlocked_at)))
+                .unwrap_or_default(),
+        };
+        lines.push(format!(
+            "{:<6} {:<10}  {:<10}  {:<10}  {:<10}  {}",
+            progression.level,
+            format_date(progression.unlocked_at),
+            format_date(progression.started_at),
+            format_date(progression.passed_at),
+            format_date(progression.completed_at),
+            time
+        ));
+    }
+
+    let durations: Vec<Duration> = progressions.iter().filter_map(level_duration).collect();
+    lines.push(String::new());
+    if let (Some(median), Some(average)) = (median(&durations), average(&durations)) {
+        lines.push(format!("Median level-up time: {}", format_duration(median)));
+        lines.push(format!(
+            "Average level-up time: {}",
+            format_duration(average)
+        ));
+    }
+
+    let recent = &durations[durations.len().saturating_sub(PACE_LEVELS)..];
+    let current = progressions
+        .iter()
+        .max_by_key(|progression| progression.level);
+    if let (Some(pace), Some(current)) = (median(recent), current) {
+        match project_max_level(current, pace, now) {
+            Some(projection) => lines.push(format!(
+                "At your current pace of {} per level you will reach level {} around {}",
+                format_duration(pace),
+                MAX_LEVEL,
+                format_date(Some(projection))
+            )),
+            None if current.level >= MAX_LEVEL => {
+                lines.push(format!("You reached level {}, congratulations!", MAX_LEVEL))
+            }
+            None => {}
+        }
+    }
+    lines.join("\n")
+}
+
+/// Shows when each level was reached and passed and projects when level 60 will be reached
+pub fn show_timeline(output_method: &str, client: &WaniKaniClient) -> Result<()> {
+    let mut progressions: Vec<LevelProgressionData> = client
+        .fetch_level_progressions()?
+        .into_iter()
+        .map(|progression| progression.data)
+        .collect();
+    // Levels left behind by a reset are shown as resets instead
+    progressions.retain(|progression| progression.abandoned_at.is_none());
+    progressions.sort_by_key(|progression| progression.level);
+    if progressions.is_empty() {
+        display::display_text(output_method, "There is no level progress yet.");
+        return Ok(());
+    }
+
+    let mut text = timeline_text(&progressions, Utc::now());
+    let resets: Vec<String> = client
+        .fetch_resets()?
+        .into_iter()
+        .filter_map(|reset| {
+            let confirmed_at = reset.data.confirmed_at?;
+            Some(format!(
+                "Reset from level {} to level {} on {}",
+                reset.data.original_level,
+                reset.data.target_level,
+                format_date(Some(confirmed_at))
+            ))
+        })
+        .collect();
+    if !resets.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&resets.join("\n"));
+    }
+    display::display_text(output_method, &text);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn progression(level: u8, unlocked_day: u32, passed_day: Option<u32>) -> LevelProgressionData {
+        let day = |day: u32| Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap();
+        LevelProgressionData {
+            created_at: day(unlocked_day),
+            level,
+            unlocked_at: Some(day(unlocked_day)),
+            started_at: Some(day(unlocked_day)),
+            passed_at: passed_day.map(day),
+            completed_at: None,
+            abandoned_at: None,
+        }
+    }
+
+    #[test]
+    fn test_median_and_average() {
+        let days = |days: &[i64]| days.iter().map(|d| Duration::days(*d)).collect::<Vec<_>>();
+        assert_eq!(median(&days(&[9, 7, 20])), Some(Duration::days(9)));
+        assert_eq!(median(&days(&[8, 10])), Some(Duration::days(9)));
+        assert_eq!(average(&days(&[7, 8, 12])), Some(Duration::days(9)));
+        assert_eq!(median(&[]), None);
+    }
+
+    #[test]
+    fn test_project_max_level() {
+        let current = progression(58, 1, None);
+        let now = Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap();
+        // Levels 58 and 59 still have to be passed
+        assert_eq!(
+            project_max_level(&current, Duration::days(7), now),
+            Some(Utc.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap())
+        );
+        // Already slower than the pace, so level 58 counts as passed now
+        let now = Utc.with_ymd_and_hms(2024, 1, 20, 0, 0, 0).unwrap();
+        assert_eq!(
+            project_max_level(&current, Duration::days(7), now),
+            Some(Utc.with_ymd_and_hms(2024, 1, 27, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            project_max_level(&progression(60, 1, None), Duration::days(7), now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_level_duration() {
+        assert_eq!(
+            level_duration(&progression(1, 1, Some(8))),
+            Some(Duration::days(7))
+        );
+        assert_eq!(level_duration(&progression(2, 8, None)), None);
+        assert_eq!(format_duration(Duration::hours(7 * 24 + 5)), "7d 5h");
+    }
+}
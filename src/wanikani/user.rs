@@ -1,6 +1,6 @@
 use crate::wanikani::decode::{from_rfc3339, from_rfc3339_option};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -32,8 +32,39 @@ pub struct Subscription {
     #[serde(rename = "type")]
     pub type_: String,
     pub max_level_granted: u8,
-    #[serde(deserialize_with = "from_rfc3339")]
-    pub period_ends_at: DateTime<Utc>,
+    /// Not set for free and lifetime subscriptions
+    #[serde(deserialize_with = "from_rfc3339_option")]
+    pub period_ends_at: Option<DateTime<Utc>>,
+}
+
+impl Subscription {
+    /// Whether a paid subscription has run out, limiting the account to the free levels
+    /// Free accounts that never subscribed are inactive too, but have nothing that ran out
+    pub fn has_lapsed(&self) -> bool {
+        self.period_ends_at
+            .is_some_and(|period_ends_at| period_ends_at < Utc::now())
+            || (!self.active && self.type_ != "free")
+    }
+}
+
+impl UserData {
+    /// Explanation shown instead of the review and lesson queues while vacation mode is on
+    pub fn vacation_notice(&self) -> Option<String> {
+        self.current_vacation_started_at.map(|started_at| {
+            format!(
+                "Vacation mode is on since {}. Your review and lesson queues are frozen until you turn it off on the WaniKani website.",
+                started_at.with_timezone(&Local).format("%Y-%m-%d")
+            )
+        })
+    }
+
+    /// Short description of the account, shown when logging in
+    pub fn summary(&self) -> String {
+        format!(
+            "Username: {}\nLevel: {}\nSubscription: {} (levels up to {})",
+            self.username, self.level, self.subscription.type_, self.subscription.max_level_granted
+        )
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -47,3 +78,35 @@ pub struct Preferences {
     pub lessons_presentation_order: String,
     pub default_voice_actor_id: u8,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn subscription(
+        active: bool,
+        type_: &str,
+        period_ends_at: Option<DateTime<Utc>>,
+    ) -> Subscription {
+        Subscription {
+            active,
+            type_: type_.to_string(),
+            max_level_granted: if active { 60 } else { 3 },
+            period_ends_at,
+        }
+    }
+
+    #[test]
+    fn test_has_lapsed() {
+        let yesterday = Utc::now() - Duration::days(1);
+        let tomorrow = Utc::now() + Duration::days(1);
+        // Never subscribed
+        assert!(!subscription(false, "free", None).has_lapsed());
+        assert!(!subscription(true, "lifetime", None).has_lapsed());
+        assert!(!subscription(true, "recurring", Some(tomorrow)).has_lapsed());
+        assert!(subscription(false, "recurring", None).has_lapsed());
+        // Went back to a free account after the subscription ended
+        assert!(subscription(false, "free", Some(yesterday)).has_lapsed());
+    }
+}
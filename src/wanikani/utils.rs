@@ -1,6 +1,8 @@
 pub mod utils {
+    use crate::wanikani::subject::subject::SubjectData;
     use anyhow::{anyhow, Result};
     use image::{DynamicImage, ImageBuffer, Luma};
+    use okanimoji::{generate_ascii_image, generate_ascii_text};
     use resvg::tiny_skia::Pixmap;
     use resvg::tiny_skia::Transform;
     use resvg::usvg::{Options, Tree};
@@ -61,15 +63,155 @@ pub mod utils {
         Ok(DynamicImage::ImageLuma8(image_buffer))
     }
 
+    /// Renders the characters of a subject as ASCII art with the given font and width
+    /// Radicals without characters are rendered from their SVG image instead
+    pub fn subject_ascii_art(data: &SubjectData, font: &str, width: u32) -> Result<String> {
+        if let Some(characters) = data.characters() {
+            return Ok(generate_ascii_text(characters, font, width as _, 2));
+        }
+
+        let image_url = match data {
+            SubjectData::Radical(radical) => &radical
+                .character_images
+                .first()
+                .ok_or_else(|| anyhow!("Radical has neither characters nor images"))?
+                .url,
+            _ => return Err(anyhow!("Subject has no characters")),
+        };
+        let response = reqwest::blocking::get(image_url)?;
+        let svg_data = response.text()?;
+        let image_ = svg_to_dynamic_image(&svg_data)?;
+        Ok(generate_ascii_image(&image_, width as _, 24, 2))
+    }
+
+    /// Plays an audio file in the background with the first available command line player
+    /// Audio is best effort, so a missing player is silently ignored
+    pub fn play_audio(url: &str) {
+        const PLAYERS: [(&str, &[&str]); 3] = [
+            ("mpv", &["--no-video", "--really-quiet"]),
+            ("ffplay", &["-nodisp", "-autoexit", "-loglevel", "quiet"]),
+            ("cvlc", &["--play-and-exit", "--quiet"]),
+        ];
+
+        for (player, args) in PLAYERS {
+            let spawned = std::process::Command::new(player)
+                .args(args)
+                .arg(url)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn();
+            if spawned.is_ok() {
+                return;
+            }
+        }
+    }
+
+    /// Editor command from `$VISUAL` or `$EDITOR`, if the user has set one
+    pub fn external_editor() -> Option<String> {
+        ["VISUAL", "EDITOR"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|editor| !editor.trim().is_empty())
+    }
+
+    /// Creates a new temporary file only the user can read holding `contents`
+    /// The file must not exist yet, so another user can't plant one to read the notes from
+    fn create_private_temp_file(contents: &str) -> Result<std::path::PathBuf> {
+        use std::io::Write;
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or_default();
+        let path =
+            std::env::temp_dir().join(format!("kanikani-{}-{}.txt", std::process::id(), nanos));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path)?;
+        file.write_all(contents.as_bytes())?;
+        Ok(path)
+    }
+
+    /// Opens `initial` in an external editor and returns the saved text
+    /// The editor command may contain arguments, such as `code --wait`
+    pub fn edit_in_external_editor(editor: &str, initial: &str) -> Result<String> {
+        let path = create_private_temp_file(initial)?;
+
+        let mut words = editor.split_whitespace();
+        let program = words.next().ok_or_else(|| anyhow!("No editor set"))?;
+        // The editor needs the terminal back in its normal mode
+        crossterm::terminal::disable_raw_mode()?;
+        let status = std::process::Command::new(program)
+            .args(words)
+            .arg(&path)
+            .status();
+
+        let text = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        if !status?.success() {
+            return Err(anyhow!("{} exited with an error", program));
+        }
+        Ok(text?.trim_end().to_string())
+    }
+
+    /// Number of single character insertions, deletions or substitutions to turn `a` into `b`
+    pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+        let b_chars: Vec<char> = b.chars().collect();
+        let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+
+        for (i, a_char) in a.chars().enumerate() {
+            let mut current = vec![i + 1; b_chars.len() + 1];
+            for (j, b_char) in b_chars.iter().enumerate() {
+                let substitution = previous[j] + usize::from(a_char != *b_char);
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            previous = current;
+        }
+        previous[b_chars.len()]
+    }
+
+    /// Validates a meaning answer, tolerating up to `max_typos` edits against each accepted meaning
+    /// An answer close to a rejected meaning is wrong unless it matches an accepted meaning exactly
+    pub fn validate_meaning(
+        user_input: &str,
+        accepted_meanings: &[String],
+        rejected_meanings: &[String],
+        max_typos: usize,
+    ) -> bool {
+        let normalized_user = normalize_string(user_input);
+        if normalized_user.is_empty() {
+            return false;
+        }
+
+        let within = |meanings: &[String], typos: usize| {
+            meanings.iter().any(|meaning| {
+                levenshtein_distance(&normalize_string(meaning), &normalized_user) <= typos
+            })
+        };
+        if within(accepted_meanings, 0) {
+            return true;
+        }
+        if within(rejected_meanings, max_typos) {
+            return false;
+        }
+        within(accepted_meanings, max_typos)
+    }
+
     /// Processes and validates user input for Japanese readings
     /// Converts both the user input and accepted readings to hiragana before comparison
     pub fn validate_reading(user_input: &str, accepted_readings: &[String]) -> bool {
-        let normalized_user = normalize_string(&user_input);
+        let normalized_user = katakana_to_hiragana(&normalize_string(user_input));
 
         // Try direct hiragana match
         let hiragana_match = accepted_readings
             .iter()
-            .any(|reading| normalize_string(reading) == normalized_user);
+            .any(|reading| katakana_to_hiragana(&normalize_string(reading)) == normalized_user);
 
         if hiragana_match {
             return true;
@@ -79,8 +221,47 @@ pub mod utils {
         let hiragana_from_romaji = romaji_to_hiragana(&normalized_user);
         accepted_readings
             .iter()
-            .map(|reading| reading) // Convert accepted readings to hiragana
-            .any(|reading| normalize_string(&reading) == hiragana_from_romaji)
+            .map(|reading| katakana_to_hiragana(&normalize_string(reading)))
+            .any(|reading| reading == hiragana_from_romaji)
+    }
+
+    /// Offset between a katakana character and the matching hiragana in Unicode
+    const KATAKANA_OFFSET: u32 = 0x60;
+
+    /// Converts katakana to hiragana, leaving everything else (including the long vowel mark) as is
+    pub fn katakana_to_hiragana(input: &str) -> String {
+        input
+            .chars()
+            .map(|c| match c {
+                'ァ'..='ヶ' => char::from_u32(c as u32 - KATAKANA_OFFSET).unwrap_or(c),
+                _ => c,
+            })
+            .collect()
+    }
+
+    /// Converts hiragana to katakana, leaving everything else as is
+    pub fn hiragana_to_katakana(input: &str) -> String {
+        input
+            .chars()
+            .map(|c| match c {
+                'ぁ'..='ゖ' => char::from_u32(c as u32 + KATAKANA_OFFSET).unwrap_or(c),
+                _ => c,
+            })
+            .collect()
+    }
+
+    /// Every kana of the romaji tables with its romaji, in hiragana and without duplicates
+    pub fn kana_table() -> Vec<(&'static str, &'static str)> {
+        let mut table: Vec<(&str, &str)> = Vec::new();
+        for (romaji, kana) in BASIC_VOWELS.iter().chain(ROMAJI_TO_HIRAGANA.iter()) {
+            match table.iter_mut().find(|(_, other)| other == kana) {
+                // Prefer the shorter spelling, e.g. "n" over "nn"
+                Some(entry) if romaji.len() < entry.0.len() => entry.0 = romaji,
+                Some(_) => {}
+                None => table.push((romaji, kana)),
+            }
+        }
+        table
     }
 
     const ROMAJI_TO_HIRAGANA: [(&str, &str); 100] = [
@@ -304,6 +485,45 @@ pub mod utils {
             assert!(!validate_reading("まちがい", &accepted));
         }
 
+        #[test]
+        fn test_kana_conversion() {
+            assert_eq!(katakana_to_hiragana("カタカナー"), "かたかなー");
+            assert_eq!(hiragana_to_katakana("きょう"), "キョウ");
+            assert_eq!(hiragana_to_katakana("kanji"), "kanji");
+        }
+
+        #[test]
+        fn test_kana_table() {
+            let table = kana_table();
+            assert_eq!(table.len(), 104);
+            assert_eq!(table[0], ("a", "あ"));
+            assert!(table.contains(&("n", "ん")));
+            assert!(!table.contains(&("nn", "ん")));
+        }
+
+        #[test]
+        fn test_levenshtein_distance() {
+            assert_eq!(levenshtein_distance("", ""), 0);
+            assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+            assert_eq!(levenshtein_distance("mountain", "mountian"), 2);
+            assert_eq!(levenshtein_distance("ground", "ground"), 0);
+        }
+
+        #[test]
+        fn test_meaning_validation() {
+            let accepted = vec!["Mountain".to_string(), "Hill".to_string()];
+            assert!(validate_meaning(" mountain ", &accepted, &[], 0));
+            assert!(!validate_meaning("mountian", &accepted, &[], 0));
+            assert!(validate_meaning("mountian", &accepted, &[], 2));
+            assert!(!validate_meaning("", &accepted, &[], 2));
+
+            // Close to an accepted meaning, but closer to a rejected one
+            let rejected = vec!["Hilt".to_string()];
+            assert!(!validate_meaning("hilt", &accepted, &rejected, 1));
+            assert!(!validate_meaning("hilts", &accepted, &rejected, 1));
+            assert!(validate_meaning("hill", &accepted, &rejected, 1));
+        }
+
         #[test]
         fn test_svg_conversion() {
             let simple_svg = r#"